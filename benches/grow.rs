@@ -35,7 +35,7 @@ fn bench_collect(c: &mut Criterion) {
         group.bench_with_input(BenchmarkId::new("Vec", i), i, |b, i| {
             let mut v = Vec::new();
             b.iter(|| {
-                v = SOME_DATA[..*i].iter().copied().collect::<Vec<&str>>();
+                v = SOME_DATA[..*i].to_vec();
             });
             drop(v);
         });
@@ -75,5 +75,37 @@ fn bench_push(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, bench_extend, bench_push, bench_collect);
+// Pushing enough elements to fill many data blocks, so that the cost of each
+// push (rather than of allocating the first few blocks) dominates.
+fn bench_push_many(c: &mut Criterion) {
+    const LEN: usize = 1 << 20;
+    let mut group = c.benchmark_group("push_many");
+    group.bench_function(BenchmarkId::new("Vec", LEN), |b| {
+        b.iter_with_large_drop(|| {
+            let mut v = Vec::<usize>::default();
+            for i in 0..LEN {
+                v.push(i);
+            }
+            v
+        })
+    });
+    group.bench_function(BenchmarkId::new("SegVec", LEN), |b| {
+        b.iter_with_large_drop(|| {
+            let mut v = SegVec::<usize>::default();
+            for i in 0..LEN {
+                v.push(i);
+            }
+            v
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_extend,
+    bench_push,
+    bench_push_many,
+    bench_collect
+);
 criterion_main!(benches);
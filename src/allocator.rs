@@ -0,0 +1,142 @@
+//! Memory allocator support.
+//!
+//! A [`SegVec`] allocates its data blocks and its index block using an
+//! [`Allocator`]. By default, this is the [`Global`] allocator.
+//!
+//! When the `allocator-api2` feature flag is enabled, the [`Allocator`] trait
+//! and [`Global`] type are re-exported from the [`allocator-api2`] crate, which
+//! provides the unstable [`std::alloc::Allocator`] API on stable Rust. This
+//! allows constructing `SegVec`s with custom allocators, using
+//! [`SegVec::new_in`] and friends.
+//!
+//! Otherwise, [`Global`] is the only allocator available: the stand-in
+//! [`Allocator`] trait is sealed, so it can't be implemented outside of this
+//! crate. This keeps enabling the `allocator-api2` feature from breaking any
+//! downstream code, since the two `Allocator` traits are different traits.
+//!
+//! [`SegVec`]: crate::SegVec
+//! [`SegVec::new_in`]: crate::SegVec::new_in
+//! [`allocator-api2`]: https://crates.io/crates/allocator-api2
+//! [`std::alloc::Allocator`]: https://doc.rust-lang.org/std/alloc/trait.Allocator.html
+use crate::TryReserveError;
+use core::{alloc::Layout, ptr::NonNull};
+
+#[cfg(feature = "allocator-api2")]
+pub use allocator_api2::alloc::{AllocError, Allocator, Global};
+
+#[cfg(not(feature = "allocator-api2"))]
+pub use self::stub::{AllocError, Allocator, Global};
+
+/// Allocates memory for an array of `capacity` `T`s.
+///
+/// If the array would be zero-sized, nothing is allocated, and a dangling
+/// pointer is returned.
+pub(crate) fn allocate<T>(
+    alloc: &impl Allocator,
+    capacity: usize,
+) -> Result<NonNull<T>, TryReserveError> {
+    let layout = Layout::array::<T>(capacity).map_err(|_| TryReserveError::capacity_overflow())?;
+    if layout.size() == 0 {
+        return Ok(NonNull::dangling());
+    }
+
+    alloc
+        .allocate(layout)
+        .map(NonNull::cast)
+        .map_err(|_| TryReserveError::alloc_error(layout))
+}
+
+/// Deallocates an array of `capacity` `T`s.
+///
+/// # Safety
+///
+/// `ptr` must have been returned by [`allocate`] with the same allocator and
+/// capacity, and must not have already been deallocated.
+pub(crate) unsafe fn deallocate<T>(alloc: &impl Allocator, ptr: NonNull<T>, capacity: usize) {
+    let layout = Layout::array::<T>(capacity)
+        .expect("layout was already checked when the array was allocated");
+    if layout.size() == 0 {
+        return;
+    }
+
+    // Safety: the caller guarantees that `ptr` was allocated by this
+    // allocator with this layout.
+    unsafe { alloc.deallocate(ptr.cast(), layout) }
+}
+
+/// A minimal version of the `Allocator` API, used when the `allocator-api2`
+/// feature is disabled.
+#[cfg(not(feature = "allocator-api2"))]
+mod stub {
+    use core::{alloc::Layout, fmt, ptr::NonNull};
+
+    /// An implementation of `Allocator` can allocate and deallocate memory.
+    ///
+    /// This is a minimal version of the unstable [`std::alloc::Allocator`]
+    /// trait. Enable the `allocator-api2` feature flag to use custom
+    /// allocators with `SegVec`.
+    ///
+    /// This trait is sealed: [`Global`] is its only implementation.
+    ///
+    /// # Safety
+    ///
+    /// Memory blocks returned from an allocator must point to valid memory
+    /// matching the requested layout, and must remain valid until they are
+    /// deallocated.
+    ///
+    /// [`std::alloc::Allocator`]: https://doc.rust-lang.org/std/alloc/trait.Allocator.html
+    pub unsafe trait Allocator: private::Sealed {
+        /// Attempts to allocate a block of memory.
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError>;
+
+        /// Deallocates the memory referenced by `ptr`.
+        ///
+        /// # Safety
+        ///
+        /// `ptr` must denote a block of memory currently allocated via this
+        /// allocator, and `layout` must fit that block of memory.
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout);
+    }
+
+    /// The global memory allocator.
+    #[derive(Copy, Clone, Default, Debug)]
+    pub struct Global;
+
+    /// The `AllocError` error indicates an allocation failure.
+    #[derive(Copy, Clone, PartialEq, Eq, Debug)]
+    pub struct AllocError;
+
+    mod private {
+        pub trait Sealed {}
+
+        impl Sealed for super::Global {}
+    }
+
+    unsafe impl Allocator for Global {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            let ptr = if layout.size() == 0 {
+                layout.align() as *mut u8
+            } else {
+                // Safety: the layout is not zero-sized.
+                unsafe { alloc::alloc::alloc(layout) }
+            };
+            NonNull::new(ptr)
+                .map(|ptr| NonNull::slice_from_raw_parts(ptr, layout.size()))
+                .ok_or(AllocError)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            if layout.size() != 0 {
+                // Safety: the caller guarantees that `ptr` was allocated by
+                // `Global::allocate` with this layout.
+                unsafe { alloc::alloc::dealloc(ptr.as_ptr(), layout) }
+            }
+        }
+    }
+
+    impl fmt::Display for AllocError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("memory allocation failed")
+        }
+    }
+}
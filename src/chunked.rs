@@ -0,0 +1,625 @@
+//! A segmented vector whose data blocks are all the same size.
+//!
+//! See [`ChunkedVec`] for details.
+use crate::{
+    allocator,
+    growth::{FixedChunks, GrowthPolicy},
+    Global, TryReserveError,
+};
+use alloc::collections::{vec_deque, VecDeque};
+use core::{
+    cmp, fmt,
+    iter::FromIterator,
+    marker::PhantomData,
+    ops::{Index, IndexMut},
+    ptr::{self, NonNull},
+    slice,
+};
+
+/// A segmented vector whose data blocks ("chunks") all hold `CHUNK`
+/// elements.
+///
+/// `CHUNK` must be a power of two, so locating an element is a shift and a
+/// mask, rather than the bit-counting of Brodnik et al.'s layout. In exchange,
+/// a `ChunkedVec` with `n` elements has `n / CHUNK` chunks, rather than
+/// _O(sqrt(n))_ data blocks.
+///
+/// Since every chunk is the same size, chunks are interchangeable:
+///
+/// - [`pop_front`](ChunkedVec::pop_front) takes constant time. Once every
+///   element in the first chunk has been removed, the whole chunk is moved to
+///   the back of the `ChunkedVec` as spare capacity, so a `ChunkedVec` which
+///   is used as a queue stops allocating once it's large enough.
+/// - Empty chunks can be moved from one `ChunkedVec` to another, with
+///   [`take_spare_chunk`](ChunkedVec::take_spare_chunk) and
+///   [`add_chunk`](ChunkedVec::add_chunk), so a pool of [`Chunk`]s can be
+///   shared between many vectors.
+///
+/// As in a [`SegVec`](crate::SegVec), elements never move once they're
+/// pushed.
+///
+/// # Examples
+///
+/// ```
+/// use segvec::ChunkedVec;
+///
+/// let mut queue: ChunkedVec<i32, 4> = (0..10).collect();
+/// assert_eq!(queue.capacity(), 12);
+///
+/// for i in 0..6 {
+///     assert_eq!(queue.pop_front(), Some(i));
+/// }
+/// assert_eq!(queue[0], 6);
+///
+/// // The first chunk was emptied, so it was moved to the back, where it can
+/// // hold more elements without allocating.
+/// assert_eq!(queue.capacity(), 10);
+/// queue.extend(10..16);
+/// assert_eq!(queue.capacity(), 10);
+/// assert!(queue.iter().copied().eq(6..16));
+/// ```
+pub struct ChunkedVec<T, const CHUNK: usize> {
+    /// The chunks, in order. Every chunk after the one containing the last
+    /// element is empty.
+    chunks: VecDeque<Chunk<T, CHUNK>>,
+
+    /// The index of the first element within the first chunk.
+    head: usize,
+
+    /// The number of elements in the `ChunkedVec`.
+    len: usize,
+}
+
+/// An empty, allocated chunk of memory, which can hold `CHUNK` elements of
+/// type `T`.
+///
+/// Chunks can be moved between [`ChunkedVec`]s to reuse their memory. A chunk
+/// is deallocated when it's dropped.
+pub struct Chunk<T, const CHUNK: usize> {
+    ptr: NonNull<T>,
+    _elements: PhantomData<T>,
+}
+
+/// An iterator over the elements of a [`ChunkedVec`].
+///
+/// This is returned by [`ChunkedVec::iter`].
+#[derive(Debug)]
+pub struct Iter<'vec, T, const CHUNK: usize> {
+    chunks: vec_deque::Iter<'vec, Chunk<T, CHUNK>>,
+    curr_chunk: slice::Iter<'vec, T>,
+    /// The index of the first element in the next chunk.
+    head: usize,
+    /// The number of elements in the chunks which haven't been visited.
+    rest: usize,
+}
+
+/// An iterator over mutable references to the elements of a [`ChunkedVec`].
+///
+/// This is returned by [`ChunkedVec::iter_mut`].
+#[derive(Debug)]
+pub struct IterMut<'vec, T, const CHUNK: usize> {
+    chunks: vec_deque::Iter<'vec, Chunk<T, CHUNK>>,
+    curr_chunk: slice::IterMut<'vec, T>,
+    /// The index of the first element in the next chunk.
+    head: usize,
+    /// The number of elements in the chunks which haven't been visited.
+    rest: usize,
+}
+
+impl<T, const CHUNK: usize> ChunkedVec<T, CHUNK> {
+    /// Constructs a new, empty `ChunkedVec`.
+    ///
+    /// The `ChunkedVec` will not allocate until elements are pushed onto it.
+    pub const fn new() -> Self {
+        Self {
+            chunks: VecDeque::new(),
+            head: 0,
+            len: 0,
+        }
+    }
+
+    /// Constructs a new, empty `ChunkedVec` with at least the specified
+    /// capacity.
+    ///
+    /// # Panics
+    ///
+    /// If allocating a chunk fails.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut this = Self::new();
+        this.reserve(capacity);
+        this
+    }
+
+    /// Returns the number of elements the `ChunkedVec` can hold without
+    /// allocating.
+    ///
+    /// The slots in the first chunk before the first element don't count,
+    /// since they can't be used until the chunk is recycled.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.chunks.len() * CHUNK - self.head
+    }
+
+    /// Returns the number of elements in the `ChunkedVec`.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the `ChunkedVec` contains no elements.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Reserves capacity for at least `additional` more elements, allocating
+    /// chunks until there is enough capacity.
+    ///
+    /// # Panics
+    ///
+    /// If the new capacity overflows a `usize`, or if allocating a chunk
+    /// fails.
+    pub fn reserve(&mut self, additional: usize) {
+        if let Err(error) = self.try_reserve(additional) {
+            error.handle();
+        }
+    }
+
+    /// Tries to reserve capacity for at least `additional` more elements,
+    /// returning an error if the capacity overflows or the allocator reports
+    /// a failure.
+    ///
+    /// If this returns an error, some chunks may still have been allocated,
+    /// but the elements are left unchanged.
+    ///
+    /// # Errors
+    ///
+    /// If the capacity overflows a `usize`, or if the allocator reports a
+    /// failure, then an error is returned.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let required = self
+            .len
+            .checked_add(additional)
+            .ok_or_else(TryReserveError::capacity_overflow)?;
+        while self.capacity() < required {
+            self.chunks.push_back(Chunk::try_new()?);
+        }
+        Ok(())
+    }
+
+    /// Returns a reference to the element at `idx`, or `None` if it is out of
+    /// bounds.
+    pub fn get(&self, idx: usize) -> Option<&T> {
+        if idx >= self.len {
+            return None;
+        }
+
+        // Safety: the element is in bounds, so it's initialized.
+        Some(unsafe { &*self.elem_ptr(idx) })
+    }
+
+    /// Returns a mutable reference to the element at `idx`, or `None` if it
+    /// is out of bounds.
+    pub fn get_mut(&mut self, idx: usize) -> Option<&mut T> {
+        if idx >= self.len {
+            return None;
+        }
+
+        // Safety: the element is in bounds, so it's initialized, and we have
+        // exclusive access to it.
+        Some(unsafe { &mut *self.elem_ptr(idx) })
+    }
+
+    /// Appends an element to the back of the `ChunkedVec`, returning its
+    /// index.
+    ///
+    /// # Panics
+    ///
+    /// If allocating a chunk fails.
+    pub fn push(&mut self, element: T) -> usize {
+        match self.try_push(element) {
+            Ok(idx) => idx,
+            Err(error) => error.handle(),
+        }
+    }
+
+    /// Appends an element to the back of the `ChunkedVec`, returning its
+    /// index, or an error if a new chunk could not be allocated.
+    ///
+    /// If this returns an error, `element` is dropped.
+    ///
+    /// # Errors
+    ///
+    /// If the capacity overflows a `usize`, or if the allocator reports a
+    /// failure, then an error is returned.
+    pub fn try_push(&mut self, element: T) -> Result<usize, TryReserveError> {
+        self.try_reserve(1)?;
+        let idx = self.len;
+        // Safety: we just reserved room for the element.
+        unsafe { self.elem_ptr(idx).write(element) };
+        self.len += 1;
+        Ok(idx)
+    }
+
+    /// Removes the last element from the `ChunkedVec` and returns it, or
+    /// `None` if it is empty.
+    ///
+    /// Chunks are not deallocated.
+    pub fn pop(&mut self) -> Option<T> {
+        let idx = self.len.checked_sub(1)?;
+        self.len = idx;
+        // Safety: the element was initialized, and is no longer owned by the
+        // `ChunkedVec`.
+        let element = unsafe { self.elem_ptr(idx).read() };
+        if self.len == 0 {
+            self.head = 0;
+        }
+        Some(element)
+    }
+
+    /// Removes the first element from the `ChunkedVec` and returns it, or
+    /// `None` if it is empty.
+    ///
+    /// This takes constant time. Every other element's index decreases by one,
+    /// but no elements are moved. Once every element in the first chunk has
+    /// been removed, the chunk is moved to the back of the `ChunkedVec`, so it
+    /// can be reused.
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+
+        // Safety: the first element is initialized, and is no longer owned by
+        // the `ChunkedVec` once `head` is advanced past it.
+        let element = unsafe { self.elem_ptr(0).read() };
+        self.len -= 1;
+        self.head += 1;
+        if self.head == CHUNK {
+            // The first chunk is now empty, so recycle it.
+            self.head = 0;
+            self.chunks.rotate_left(1);
+        } else if self.len == 0 {
+            self.head = 0;
+        }
+        Some(element)
+    }
+
+    /// Removes every element from the `ChunkedVec`.
+    ///
+    /// Chunks are not deallocated.
+    pub fn clear(&mut self) {
+        let (head, len) = (self.head, self.len);
+        // If dropping an element panics, the rest are leaked, rather than
+        // dropped twice.
+        self.head = 0;
+        self.len = 0;
+        let mut rest = len;
+        let mut start = head;
+        for chunk in &self.chunks {
+            if rest == 0 {
+                break;
+            }
+
+            let chunk_len = cmp::min(rest, CHUNK - start);
+            // Safety: the elements in `start..start + chunk_len` were
+            // initialized, and are no longer owned by the `ChunkedVec`.
+            unsafe {
+                ptr::drop_in_place(ptr::slice_from_raw_parts_mut(
+                    chunk.ptr.as_ptr().add(start),
+                    chunk_len,
+                ))
+            };
+            rest -= chunk_len;
+            start = 0;
+        }
+    }
+
+    /// Removes an empty chunk from the back of the `ChunkedVec`, if it has
+    /// any, so that it can be added to another `ChunkedVec`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use segvec::ChunkedVec;
+    ///
+    /// let mut a: ChunkedVec<u8, 16> = ChunkedVec::with_capacity(32);
+    /// a.push(1);
+    ///
+    /// let mut b = ChunkedVec::new();
+    /// while let Some(chunk) = a.take_spare_chunk() {
+    ///     b.add_chunk(chunk);
+    /// }
+    ///
+    /// assert_eq!(a.capacity(), 16);
+    /// assert_eq!(b.capacity(), 16);
+    /// ```
+    pub fn take_spare_chunk(&mut self) -> Option<Chunk<T, CHUNK>> {
+        if self.chunks.len() <= self.used_chunks() {
+            return None;
+        }
+
+        self.chunks.pop_back()
+    }
+
+    /// Adds an empty chunk to the back of the `ChunkedVec`, increasing its
+    /// capacity by `CHUNK` elements.
+    pub fn add_chunk(&mut self, chunk: Chunk<T, CHUNK>) {
+        self.chunks.push_back(chunk);
+    }
+
+    /// Deallocates every empty chunk at the back of the `ChunkedVec`.
+    pub fn shrink_to_fit(&mut self) {
+        self.chunks.truncate(self.used_chunks());
+        self.chunks.shrink_to_fit();
+    }
+
+    /// Returns an iterator over the elements in the `ChunkedVec`.
+    pub fn iter(&self) -> Iter<'_, T, CHUNK> {
+        Iter {
+            chunks: self.chunks.iter(),
+            curr_chunk: [].iter(),
+            head: self.head,
+            rest: self.len,
+        }
+    }
+
+    /// Returns an iterator over mutable references to the elements in the
+    /// `ChunkedVec`.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T, CHUNK> {
+        IterMut {
+            chunks: self.chunks.iter(),
+            curr_chunk: [].iter_mut(),
+            head: self.head,
+            rest: self.len,
+        }
+    }
+
+    /// Returns the number of chunks which contain at least one element.
+    fn used_chunks(&self) -> usize {
+        if self.len == 0 {
+            return 0;
+        }
+
+        FixedChunks::<CHUNK>::locate(self.head + self.len - 1).0 + 1
+    }
+
+    /// Returns a pointer to the element at `idx`.
+    ///
+    /// # Safety
+    ///
+    /// `idx` must be less than the `ChunkedVec`'s capacity.
+    unsafe fn elem_ptr(&self, idx: usize) -> *mut T {
+        let (chunk, slot) = FixedChunks::<CHUNK>::locate(self.head + idx);
+        debug_assert!(chunk < self.chunks.len());
+        // Safety: guaranteed by the caller.
+        unsafe {
+            self.chunks
+                .get(chunk)
+                .unwrap_unchecked()
+                .ptr
+                .as_ptr()
+                .add(slot)
+        }
+    }
+}
+
+impl<T, const CHUNK: usize> Index<usize> for ChunkedVec<T, CHUNK> {
+    type Output = T;
+
+    #[track_caller]
+    fn index(&self, idx: usize) -> &Self::Output {
+        match self.get(idx) {
+            None => panic!(
+                "ChunkedVec index out of bounds: the len is {} but the index is {}",
+                self.len(),
+                idx
+            ),
+            Some(elem) => elem,
+        }
+    }
+}
+
+impl<T, const CHUNK: usize> IndexMut<usize> for ChunkedVec<T, CHUNK> {
+    #[track_caller]
+    fn index_mut(&mut self, idx: usize) -> &mut Self::Output {
+        let len = self.len();
+        match self.get_mut(idx) {
+            None => panic!(
+                "ChunkedVec index out of bounds: the len is {} but the index is {}",
+                len, idx
+            ),
+            Some(elem) => elem,
+        }
+    }
+}
+
+impl<T, const CHUNK: usize> Extend<T> for ChunkedVec<T, CHUNK> {
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let iter = iter.into_iter();
+        self.reserve(crate::size_hint_capacity(&iter));
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+impl<T, const CHUNK: usize> FromIterator<T> for ChunkedVec<T, CHUNK> {
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let mut this = Self::new();
+        this.extend(iter);
+        this
+    }
+}
+
+impl<'vec, T, const CHUNK: usize> IntoIterator for &'vec ChunkedVec<T, CHUNK> {
+    type IntoIter = Iter<'vec, T, CHUNK>;
+    type Item = &'vec T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'vec, T, const CHUNK: usize> IntoIterator for &'vec mut ChunkedVec<T, CHUNK> {
+    type IntoIter = IterMut<'vec, T, CHUNK>;
+    type Item = &'vec mut T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<T: Clone, const CHUNK: usize> Clone for ChunkedVec<T, CHUNK> {
+    fn clone(&self) -> Self {
+        let mut clone = Self::with_capacity(self.len());
+        clone.extend(self.iter().cloned());
+        clone
+    }
+}
+
+impl<T: fmt::Debug, const CHUNK: usize> fmt::Debug for ChunkedVec<T, CHUNK> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T, const CHUNK: usize> Default for ChunkedVec<T, CHUNK> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const CHUNK: usize> Drop for ChunkedVec<T, CHUNK> {
+    fn drop(&mut self) {
+        // The chunks deallocate themselves, but they don't know which of
+        // their slots are initialized.
+        self.clear();
+    }
+}
+
+// === impl Chunk ===
+
+impl<T, const CHUNK: usize> Chunk<T, CHUNK> {
+    /// Allocates a new chunk.
+    ///
+    /// # Panics
+    ///
+    /// If allocating the chunk fails.
+    pub fn new() -> Self {
+        match Self::try_new() {
+            Ok(chunk) => chunk,
+            Err(error) => error.handle(),
+        }
+    }
+
+    /// Allocates a new chunk, returning an error if the allocator reports a
+    /// failure.
+    ///
+    /// # Errors
+    ///
+    /// If `CHUNK` elements overflow `isize::MAX` _bytes_, or if the allocator
+    /// reports a failure, then an error is returned.
+    pub fn try_new() -> Result<Self, TryReserveError> {
+        Ok(Self {
+            ptr: allocator::allocate(&Global, CHUNK)?,
+            _elements: PhantomData,
+        })
+    }
+}
+
+impl<T, const CHUNK: usize> Default for Chunk<T, CHUNK> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const CHUNK: usize> Drop for Chunk<T, CHUNK> {
+    fn drop(&mut self) {
+        // Safety: the chunk was allocated with this capacity, and it's empty.
+        unsafe { allocator::deallocate(&Global, self.ptr, CHUNK) };
+    }
+}
+
+impl<T, const CHUNK: usize> fmt::Debug for Chunk<T, CHUNK> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Chunk").field(&self.ptr).finish()
+    }
+}
+
+// Safety: a chunk is just memory; the `ChunkedVec` which owns it owns the
+// elements in it, and only provides access to them through references to the
+// `ChunkedVec`, just like `Vec`.
+unsafe impl<T: Send, const CHUNK: usize> Send for Chunk<T, CHUNK> {}
+unsafe impl<T: Sync, const CHUNK: usize> Sync for Chunk<T, CHUNK> {}
+
+// === impl Iter ===
+
+impl<'vec, T, const CHUNK: usize> Iterator for Iter<'vec, T, CHUNK> {
+    type Item = &'vec T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(elem) = self.curr_chunk.next() {
+                return Some(elem);
+            }
+            if self.rest == 0 {
+                return None;
+            }
+
+            let chunk = self.chunks.next()?;
+            let len = cmp::min(self.rest, CHUNK - self.head);
+            // Safety: the elements in `head..head + len` are initialized.
+            self.curr_chunk =
+                unsafe { slice::from_raw_parts(chunk.ptr.as_ptr().add(self.head), len) }.iter();
+            self.rest -= len;
+            self.head = 0;
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.curr_chunk.len() + self.rest;
+        (len, Some(len))
+    }
+}
+
+impl<T, const CHUNK: usize> ExactSizeIterator for Iter<'_, T, CHUNK> {}
+
+// === impl IterMut ===
+
+impl<'vec, T, const CHUNK: usize> Iterator for IterMut<'vec, T, CHUNK> {
+    type Item = &'vec mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(elem) = self.curr_chunk.next() {
+                return Some(elem);
+            }
+            if self.rest == 0 {
+                return None;
+            }
+
+            let chunk = self.chunks.next()?;
+            let len = cmp::min(self.rest, CHUNK - self.head);
+            // Safety: the elements in `head..head + len` are initialized, and
+            // the iterator borrows the `ChunkedVec` mutably.
+            self.curr_chunk =
+                unsafe { slice::from_raw_parts_mut(chunk.ptr.as_ptr().add(self.head), len) }
+                    .iter_mut();
+            self.rest -= len;
+            self.head = 0;
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.curr_chunk.len() + self.rest;
+        (len, Some(len))
+    }
+}
+
+impl<T, const CHUNK: usize> ExactSizeIterator for IterMut<'_, T, CHUNK> {}
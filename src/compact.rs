@@ -0,0 +1,614 @@
+//! A segmented vector with a compact header, for containers which hold many
+//! small vectors.
+//!
+//! See [`SegVec32`] for details.
+use crate::{allocator, location::Location, Global, TryReserveError};
+use core::{
+    cmp,
+    convert::TryFrom,
+    fmt,
+    iter::FromIterator,
+    marker::PhantomData,
+    mem,
+    ops::{Index, IndexMut},
+    ptr::{self, NonNull},
+    slice,
+};
+
+/// A segmented vector with a compact header, whose length is limited to
+/// `u32::MAX` elements.
+///
+/// A [`SegVec`](crate::SegVec) keeps a fair amount of metadata alongside its
+/// elements, so that it can skip low-size data blocks and track each data
+/// block's length. When a program keeps millions of small vectors inside
+/// other data structures, that header can take up more memory than the
+/// elements themselves.
+///
+/// A `SegVec32` stores only a pointer to its index block, its length, and the
+/// number of data blocks it has allocated, which is 16 bytes on 64-bit
+/// targets. Everything else is computed from those:
+///
+/// - Data blocks are always laid out as in Brodnik et al.'s paper, starting
+///   with the first data block which can hold as many elements as a `Vec`
+///   allocates for its first push, so the capacity of each data block is
+///   determined by its position in the index and the size of `T`.
+/// - The index block holds bare pointers to the data blocks. Each data block
+///   is full, except for the one containing the last element, so the length
+///   of each data block is determined by the `SegVec32`'s length.
+/// - The index block's capacity is the number of data blocks, rounded up to
+///   a power of two.
+///
+/// As in a `SegVec`, elements never move once they're pushed.
+///
+/// # Examples
+///
+/// ```
+/// use segvec::SegVec32;
+///
+/// let mut segvec = SegVec32::new();
+/// for i in 0..10 {
+///     segvec.push(i);
+/// }
+///
+/// assert_eq!(segvec.len(), 10);
+/// assert_eq!(segvec[3], 3);
+/// assert_eq!(segvec.pop(), Some(9));
+/// assert!(segvec.iter().copied().eq(0..9));
+///
+/// #[cfg(target_pointer_width = "64")]
+/// assert_eq!(std::mem::size_of::<SegVec32<u64>>(), 16);
+/// ```
+pub struct SegVec32<T> {
+    /// The "index block", which holds pointers to the data blocks. It has
+    /// room for `blocks` pointers, rounded up to a power of two.
+    index: NonNull<NonNull<T>>,
+
+    /// The number of elements in the `SegVec32`.
+    len: u32,
+
+    /// The number of data blocks which have been allocated.
+    blocks: u32,
+
+    /// The `SegVec32` owns the elements in its data blocks.
+    _elements: PhantomData<T>,
+}
+
+/// An iterator over the elements of a [`SegVec32`].
+///
+/// This is returned by [`SegVec32::iter`].
+#[derive(Debug)]
+pub struct Iter<'segvec, T> {
+    blocks: slice::Iter<'segvec, NonNull<T>>,
+    /// The location of the next data block to visit.
+    next_block: Location,
+    curr_block: slice::Iter<'segvec, T>,
+    /// The number of elements in the data blocks which haven't been visited.
+    rest: usize,
+}
+
+/// An iterator over mutable references to the elements of a [`SegVec32`].
+///
+/// This is returned by [`SegVec32::iter_mut`].
+#[derive(Debug)]
+pub struct IterMut<'segvec, T> {
+    blocks: slice::Iter<'segvec, NonNull<T>>,
+    /// The location of the next data block to visit.
+    next_block: Location,
+    curr_block: slice::IterMut<'segvec, T>,
+    /// The number of elements in the data blocks which haven't been visited.
+    rest: usize,
+}
+
+impl<T> SegVec32<T> {
+    /// The maximum number of elements in a `SegVec32`.
+    pub const MAX_LEN: usize = u32::MAX as usize;
+
+    // Minimum size of the first data block, as in `SegVec`.
+    // Zero-sized elements don't take up any memory, so there's nothing to
+    // gain from skipping data blocks for them.
+    const MIN_NON_ZERO_CAP: usize = if mem::size_of::<T>() == 0 {
+        1
+    } else if mem::size_of::<T>() == 1 {
+        8
+    } else if mem::size_of::<T>() <= 1024 {
+        4
+    } else {
+        1
+    };
+
+    // The superblock holding the first data block.
+    // Data blocks of `2^m` elements first appear in superblock `2m - 1`.
+    const FIRST_SUPERBLOCK: usize = match Self::MIN_NON_ZERO_CAP.trailing_zeros() {
+        0 => 0,
+        m => 2 * m as usize - 1,
+    };
+
+    // The number of data blocks, and the number of indices they hold, which
+    // come before the first data block in the Brodnik layout. These aren't
+    // allocated, and aren't stored in the index.
+    const SKIPPED_BLOCKS: usize = Location::block_len(Self::FIRST_SUPERBLOCK)
+        + Location::blocks_in(Self::FIRST_SUPERBLOCK)
+        - 2;
+    const SKIPPED_INDICES: usize = Location::start_of(Self::FIRST_SUPERBLOCK, 0);
+
+    /// Constructs a new, empty `SegVec32`.
+    ///
+    /// The `SegVec32` will not allocate until elements are pushed onto it.
+    pub const fn new() -> Self {
+        Self {
+            index: NonNull::dangling(),
+            len: 0,
+            blocks: 0,
+            _elements: PhantomData,
+        }
+    }
+
+    /// Constructs a new, empty `SegVec32` with at least the specified
+    /// capacity.
+    ///
+    /// # Panics
+    ///
+    /// If `capacity` exceeds [`SegVec32::MAX_LEN`], or if the new capacity
+    /// exceeds `isize::MAX` bytes.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut this = Self::new();
+        this.reserve(capacity);
+        this
+    }
+
+    /// Returns the number of elements the `SegVec32` can hold without
+    /// allocating.
+    pub fn capacity(&self) -> usize {
+        let end = Location::of_data_block(self.blocks as usize + Self::SKIPPED_BLOCKS);
+        Location::start_of(end.superblock, end.block) - Self::SKIPPED_INDICES
+    }
+
+    /// Returns the number of elements in the `SegVec32`.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    /// Returns `true` if the `SegVec32` contains no elements.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Reserves capacity for at least `additional` more elements.
+    ///
+    /// Data blocks are added, in order, until there is enough capacity.
+    ///
+    /// # Panics
+    ///
+    /// If the new capacity exceeds [`SegVec32::MAX_LEN`] or `isize::MAX`
+    /// bytes.
+    pub fn reserve(&mut self, additional: usize) {
+        if let Err(error) = self.try_reserve(additional) {
+            error.handle();
+        }
+    }
+
+    /// Tries to reserve capacity for at least `additional` more elements,
+    /// returning an error if the capacity overflows or the allocator reports
+    /// a failure.
+    ///
+    /// If this returns an error, the `SegVec32` is left unchanged.
+    ///
+    /// # Errors
+    ///
+    /// If the capacity overflows [`SegVec32::MAX_LEN`] or `isize::MAX`
+    /// _bytes_, or if the allocator reports a failure, then an error is
+    /// returned.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        if additional == 0 {
+            return Ok(());
+        }
+
+        let required = self
+            .len()
+            .checked_add(additional)
+            // This is `required <= Self::MAX_LEN`, which clippy rejects as
+            // always true on 32-bit targets.
+            .filter(|&required| u32::try_from(required).is_ok())
+            .ok_or_else(TryReserveError::capacity_overflow)?;
+        required
+            .checked_mul(mem::size_of::<T>())
+            .filter(|&bytes| bytes <= isize::MAX as usize)
+            .ok_or_else(TryReserveError::capacity_overflow)?;
+
+        let last_block = Self::locate(required - 1).data_block() - Self::SKIPPED_BLOCKS;
+        if last_block < self.blocks as usize {
+            return Ok(());
+        }
+        self.try_alloc_blocks(last_block + 1)
+    }
+
+    /// Returns a reference to the element at `idx`, or `None` if it is out of
+    /// bounds.
+    pub fn get(&self, idx: usize) -> Option<&T> {
+        if idx >= self.len() {
+            return None;
+        }
+
+        // Safety: the element is in bounds, so it's initialized.
+        Some(unsafe { &*self.elem_ptr(idx) })
+    }
+
+    /// Returns a mutable reference to the element at `idx`, or `None` if it
+    /// is out of bounds.
+    pub fn get_mut(&mut self, idx: usize) -> Option<&mut T> {
+        if idx >= self.len() {
+            return None;
+        }
+
+        // Safety: the element is in bounds, so it's initialized, and we have
+        // exclusive access to it.
+        Some(unsafe { &mut *self.elem_ptr(idx) })
+    }
+
+    /// Appends an element to the back of the `SegVec32`, returning its index.
+    ///
+    /// # Panics
+    ///
+    /// If the `SegVec32` already holds [`SegVec32::MAX_LEN`] elements, or if
+    /// the new capacity exceeds `isize::MAX` bytes.
+    pub fn push(&mut self, element: T) -> usize {
+        match self.try_push(element) {
+            Ok(idx) => idx,
+            Err(error) => error.handle(),
+        }
+    }
+
+    /// Appends an element to the back of the `SegVec32`, returning its
+    /// index, or an error if a new data block could not be allocated.
+    ///
+    /// If this returns an error, `element` is dropped.
+    ///
+    /// # Errors
+    ///
+    /// If the `SegVec32` already holds [`SegVec32::MAX_LEN`] elements, if the
+    /// new capacity overflows `isize::MAX` _bytes_, or if the allocator
+    /// reports a failure, then an error is returned.
+    pub fn try_push(&mut self, element: T) -> Result<usize, TryReserveError> {
+        self.try_reserve(1)?;
+        let idx = self.len();
+        // Safety: we just reserved room for the element.
+        unsafe { self.elem_ptr(idx).write(element) };
+        self.len += 1;
+        Ok(idx)
+    }
+
+    /// Removes the last element from the `SegVec32` and returns it, or `None`
+    /// if it is empty.
+    ///
+    /// Data blocks are not deallocated.
+    pub fn pop(&mut self) -> Option<T> {
+        let idx = self.len().checked_sub(1)?;
+        self.len -= 1;
+        // Safety: the element was initialized, and is no longer owned by the
+        // `SegVec32`.
+        Some(unsafe { self.elem_ptr(idx).read() })
+    }
+
+    /// Removes every element from the `SegVec32`.
+    ///
+    /// Data blocks are not deallocated.
+    pub fn clear(&mut self) {
+        let len = self.len();
+        // If dropping an element panics, the rest are leaked, rather than
+        // dropped twice.
+        self.len = 0;
+        let mut loc = Self::locate(0);
+        let mut rest = len;
+        for &block in self.blocks() {
+            if rest == 0 {
+                break;
+            }
+
+            let block_len = cmp::min(rest, Location::block_len(loc.superblock));
+            // Safety: the first `block_len` elements in the block were
+            // initialized, and are no longer owned by the `SegVec32`.
+            unsafe { ptr::drop_in_place(ptr::slice_from_raw_parts_mut(block.as_ptr(), block_len)) };
+            rest -= block_len;
+            loc = loc.next_block();
+        }
+    }
+
+    /// Returns an iterator over the elements in the `SegVec32`.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            blocks: self.blocks().iter(),
+            next_block: Self::locate(0),
+            curr_block: [].iter(),
+            rest: self.len(),
+        }
+    }
+
+    /// Returns an iterator over mutable references to the elements in the
+    /// `SegVec32`.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            blocks: self.blocks().iter(),
+            next_block: Self::locate(0),
+            curr_block: [].iter_mut(),
+            rest: self.len(),
+        }
+    }
+
+    /// Returns the pointers to the data blocks.
+    fn blocks(&self) -> &[NonNull<T>] {
+        // Safety: the first `self.blocks` entries in the index are
+        // initialized.
+        unsafe { slice::from_raw_parts(self.index.as_ptr(), self.blocks as usize) }
+    }
+
+    /// Returns a pointer to the element at `idx`.
+    ///
+    /// # Safety
+    ///
+    /// `idx` must be less than the `SegVec32`'s capacity.
+    unsafe fn elem_ptr(&self, idx: usize) -> *mut T {
+        let loc = Self::locate(idx);
+        let block = loc.data_block() - Self::SKIPPED_BLOCKS;
+        debug_assert!(block < self.blocks as usize);
+        // Safety: guaranteed by the caller.
+        unsafe { self.blocks().get_unchecked(block).as_ptr().add(loc.slot) }
+    }
+
+    /// Returns the location of the element at `idx` in the Brodnik layout,
+    /// including the skipped data blocks.
+    #[inline]
+    fn locate(idx: usize) -> Location {
+        Location::of(idx + Self::SKIPPED_INDICES)
+    }
+
+    /// Allocates data blocks until there are `blocks` of them.
+    ///
+    /// If this returns an error, every data block it allocated is freed
+    /// again, and the `SegVec32` is left unchanged.
+    fn try_alloc_blocks(&mut self, blocks: usize) -> Result<(), TryReserveError> {
+        let old_blocks = self.blocks as usize;
+        let old_capacity = index_capacity(self.blocks);
+        let new_capacity = index_capacity(blocks as u32);
+
+        // If the index block is too small, allocate a bigger one, but keep
+        // the old one until every new data block has been allocated.
+        let index = if new_capacity > old_capacity {
+            let index = allocator::allocate::<NonNull<T>>(&Global, new_capacity)?;
+            // Safety: the new index block has room for more than
+            // `old_blocks` entries.
+            unsafe { ptr::copy_nonoverlapping(self.index.as_ptr(), index.as_ptr(), old_blocks) };
+            index
+        } else {
+            self.index
+        };
+
+        let mut loc = Location::of_data_block(old_blocks + Self::SKIPPED_BLOCKS);
+        for i in old_blocks..blocks {
+            match allocator::allocate::<T>(&Global, Location::block_len(loc.superblock)) {
+                // Safety: the index block has room for `blocks` entries.
+                Ok(block) => unsafe { index.as_ptr().add(i).write(block) },
+                Err(error) => {
+                    // Safety: the data blocks after `old_blocks` were just
+                    // allocated with the capacities given by their positions,
+                    // and a new index block with `new_capacity` entries.
+                    unsafe {
+                        let mut loc = Location::of_data_block(old_blocks + Self::SKIPPED_BLOCKS);
+                        for j in old_blocks..i {
+                            let block = index.as_ptr().add(j).read();
+                            allocator::deallocate(
+                                &Global,
+                                block,
+                                Location::block_len(loc.superblock),
+                            );
+                            loc = loc.next_block();
+                        }
+                        if index != self.index {
+                            allocator::deallocate(&Global, index, new_capacity);
+                        }
+                    }
+                    return Err(error);
+                }
+            }
+            loc = loc.next_block();
+        }
+
+        if index != self.index {
+            // Safety: the old index block was allocated with this capacity.
+            unsafe { allocator::deallocate(&Global, self.index, old_capacity) };
+            self.index = index;
+        }
+        // This can't truncate: `try_reserve` checked that the capacity fits
+        // in a `u32`, and each data block holds at least one element.
+        self.blocks = blocks as u32;
+        Ok(())
+    }
+}
+
+/// Returns the capacity of the index block of a `SegVec32` with `blocks`
+/// data blocks.
+fn index_capacity(blocks: u32) -> usize {
+    if blocks == 0 {
+        0
+    } else {
+        blocks.next_power_of_two() as usize
+    }
+}
+
+impl<T> Index<usize> for SegVec32<T> {
+    type Output = T;
+
+    #[track_caller]
+    fn index(&self, idx: usize) -> &Self::Output {
+        match self.get(idx) {
+            None => panic!(
+                "SegVec32 index out of bounds: the len is {} but the index is {}",
+                self.len(),
+                idx
+            ),
+            Some(elem) => elem,
+        }
+    }
+}
+
+impl<T> IndexMut<usize> for SegVec32<T> {
+    #[track_caller]
+    fn index_mut(&mut self, idx: usize) -> &mut Self::Output {
+        let len = self.len();
+        match self.get_mut(idx) {
+            None => panic!(
+                "SegVec32 index out of bounds: the len is {} but the index is {}",
+                len, idx
+            ),
+            Some(elem) => elem,
+        }
+    }
+}
+
+impl<T> Extend<T> for SegVec32<T> {
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let iter = iter.into_iter();
+        self.reserve(crate::size_hint_capacity(&iter));
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for SegVec32<T> {
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let mut this = Self::new();
+        this.extend(iter);
+        this
+    }
+}
+
+impl<'segvec, T> IntoIterator for &'segvec SegVec32<T> {
+    type IntoIter = Iter<'segvec, T>;
+    type Item = &'segvec T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'segvec, T> IntoIterator for &'segvec mut SegVec32<T> {
+    type IntoIter = IterMut<'segvec, T>;
+    type Item = &'segvec mut T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<T: Clone> Clone for SegVec32<T> {
+    fn clone(&self) -> Self {
+        let mut clone = Self::with_capacity(self.len());
+        clone.extend(self.iter().cloned());
+        clone
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SegVec32<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T> Default for SegVec32<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for SegVec32<T> {
+    fn drop(&mut self) {
+        self.clear();
+
+        let mut loc = Self::locate(0);
+        for &block in self.blocks() {
+            // Safety: each data block was allocated with the capacity given
+            // by its position in the index.
+            unsafe { allocator::deallocate(&Global, block, Location::block_len(loc.superblock)) };
+            loc = loc.next_block();
+        }
+        // Safety: the index block was allocated with this capacity.
+        unsafe { allocator::deallocate(&Global, self.index, index_capacity(self.blocks)) };
+    }
+}
+
+// Safety: a `SegVec32` owns its elements, and only provides access to them
+// through references to the `SegVec32`, just like `Vec`.
+unsafe impl<T: Send> Send for SegVec32<T> {}
+unsafe impl<T: Sync> Sync for SegVec32<T> {}
+
+// === impl Iter ===
+
+impl<'segvec, T> Iterator for Iter<'segvec, T> {
+    type Item = &'segvec T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(elem) = self.curr_block.next() {
+                return Some(elem);
+            }
+            if self.rest == 0 {
+                return None;
+            }
+
+            let block = self.blocks.next()?;
+            let len = cmp::min(self.rest, Location::block_len(self.next_block.superblock));
+            self.rest -= len;
+            self.next_block = self.next_block.next_block();
+            // Safety: every element before the `SegVec32`'s length is
+            // initialized.
+            self.curr_block = unsafe { slice::from_raw_parts(block.as_ptr(), len) }.iter();
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.curr_block.len() + self.rest;
+        (len, Some(len))
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+// === impl IterMut ===
+
+impl<'segvec, T> Iterator for IterMut<'segvec, T> {
+    type Item = &'segvec mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(elem) = self.curr_block.next() {
+                return Some(elem);
+            }
+            if self.rest == 0 {
+                return None;
+            }
+
+            let block = self.blocks.next()?;
+            let len = cmp::min(self.rest, Location::block_len(self.next_block.superblock));
+            self.rest -= len;
+            self.next_block = self.next_block.next_block();
+            // Safety: every element before the `SegVec32`'s length is
+            // initialized, and the iterator borrows the `SegVec32` mutably.
+            self.curr_block = unsafe { slice::from_raw_parts_mut(block.as_ptr(), len) }.iter_mut();
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.curr_block.len() + self.rest;
+        (len, Some(len))
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
@@ -0,0 +1,249 @@
+//! An append-only segmented vector which can be pushed to concurrently.
+//!
+//! See [`ConcurrentSegVec`] for details.
+use crate::fixed_index::FixedIndex;
+use core::{
+    cell::UnsafeCell,
+    fmt,
+    mem::MaybeUninit,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering::*},
+};
+
+/// An append-only segmented vector which can be pushed to concurrently.
+///
+/// Because data blocks in a segmented vector never move once they're
+/// allocated, pushing an element never invalidates references to existing
+/// elements. A `ConcurrentSegVec` takes advantage of this to allow pushing
+/// through a shared reference, from any number of threads at once, without
+/// locking.
+///
+/// Each push reserves an index by atomically incrementing the vector's
+/// length, writes the element to that index, and then _publishes_ it. An
+/// element is only visible to [`get`](Self::get) once it's been published, so
+/// an index less than [`len`](Self::len) may briefly return `None`, while the
+/// thread that reserved it is still writing the element.
+///
+/// Data blocks are allocated lazily, the first time an index in them is
+/// reserved, through a fixed-size index which never needs to be reallocated.
+/// If several threads need the same new data block at once, each allocates
+/// one, and all but the first to publish theirs free them again, so a push
+/// never waits for another thread.
+///
+/// # Examples
+///
+/// ```
+/// use segvec::ConcurrentSegVec;
+/// use std::thread;
+///
+/// let segvec = ConcurrentSegVec::new();
+/// thread::scope(|scope| {
+///     for t in 0..4 {
+///         let segvec = &segvec;
+///         scope.spawn(move || {
+///             for i in 0..100 {
+///                 segvec.push(t * 100 + i);
+///             }
+///         });
+///     }
+/// });
+///
+/// assert_eq!(segvec.len(), 400);
+/// let mut elements: Vec<_> = segvec.iter().copied().collect();
+/// elements.sort();
+/// assert!(elements.into_iter().eq(0..400));
+/// ```
+pub struct ConcurrentSegVec<T> {
+    /// The number of indices that have been reserved.
+    len: AtomicUsize,
+    index: FixedIndex<Slot<T>>,
+}
+
+/// An iterator over the published elements of a [`ConcurrentSegVec`].
+///
+/// This is returned by [`ConcurrentSegVec::iter`].
+#[derive(Debug)]
+pub struct Iter<'segvec, T> {
+    segvec: &'segvec ConcurrentSegVec<T>,
+    idx: usize,
+    len: usize,
+}
+
+struct Slot<T> {
+    /// Set once `value` has been written.
+    published: AtomicBool,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+// Safety: elements can be pushed from any thread, and are dropped by
+// whichever thread drops the `ConcurrentSegVec`, so `T` must be `Send`.
+unsafe impl<T: Send> Send for ConcurrentSegVec<T> {}
+
+// Safety: sharing a `ConcurrentSegVec` allows both pushing elements from other
+// threads, which requires `T: Send`, and accessing elements from other
+// threads, which requires `T: Sync`. A slot's value is only written by the
+// thread which reserved its index, before it's published.
+unsafe impl<T: Send + Sync> Sync for ConcurrentSegVec<T> {}
+
+impl<T> ConcurrentSegVec<T> {
+    /// Constructs a new, empty `ConcurrentSegVec`.
+    ///
+    /// The `ConcurrentSegVec` will not allocate until elements are pushed
+    /// onto it.
+    pub const fn new() -> Self {
+        Self {
+            len: AtomicUsize::new(0),
+            index: FixedIndex::new(),
+        }
+    }
+
+    /// Appends an element to the back of the collection, returning its index.
+    ///
+    /// This may be called concurrently from multiple threads. The element is
+    /// visible to other threads as soon as this method returns.
+    ///
+    /// # Panics
+    ///
+    /// If allocating a new data block fails, or if the number of elements
+    /// overflows a `usize`.
+    pub fn push(&self, value: T) -> usize {
+        // Reserve an index, refusing to wrap the length around, so that an
+        // index is never handed out twice.
+        let idx = self
+            .len
+            .fetch_update(Relaxed, Relaxed, |len| len.checked_add(1))
+            .expect("ConcurrentSegVec length overflow");
+
+        let slot = self.index.get_or_alloc(idx);
+        // Safety: this thread reserved `idx`, so no other thread will write
+        // to the slot, and no thread will read it until it's published.
+        unsafe { (*slot.value.get()).write(value) };
+        slot.published.store(true, Release);
+        idx
+    }
+
+    /// Returns a reference to the element at `idx`, or `None` if there is no
+    /// element at `idx`, or if it hasn't been published yet.
+    pub fn get(&self, idx: usize) -> Option<&T> {
+        if idx >= self.len() {
+            return None;
+        }
+
+        let slot = self.index.get(idx)?;
+        if !slot.published.load(Acquire) {
+            return None;
+        }
+
+        // Safety: the slot's value was written before it was published, and
+        // it will never be written again.
+        Some(unsafe { (*slot.value.get()).assume_init_ref() })
+    }
+
+    /// Returns a mutable reference to the element at `idx`, or `None` if
+    /// there is no element at `idx`.
+    pub fn get_mut(&mut self, idx: usize) -> Option<&mut T> {
+        if idx >= self.len() {
+            return None;
+        }
+
+        let slot = self.index.get_mut(idx)?;
+        if !*slot.published.get_mut() {
+            return None;
+        }
+
+        // Safety: the slot's value has been published, and we have exclusive
+        // access to it.
+        Some(unsafe { slot.value.get_mut().assume_init_mut() })
+    }
+
+    /// Returns the number of indices that have been reserved by [`push`].
+    ///
+    /// Some of those elements may not have been published yet, if another
+    /// thread is currently pushing.
+    ///
+    /// [`push`]: Self::push
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len.load(Acquire)
+    }
+
+    /// Returns `true` if no elements have been pushed.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns an iterator over the published elements.
+    ///
+    /// The iterator visits the elements whose indices had been reserved when
+    /// this method was called, in index order, skipping any which haven't been
+    /// published yet.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            segvec: self,
+            idx: 0,
+            len: self.len(),
+        }
+    }
+}
+
+impl<T> Default for ConcurrentSegVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ConcurrentSegVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'segvec, T> IntoIterator for &'segvec ConcurrentSegVec<T> {
+    type IntoIter = Iter<'segvec, T>;
+    type Item = &'segvec T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+// === impl Iter ===
+
+impl<'segvec, T> Iterator for Iter<'segvec, T> {
+    type Item = &'segvec T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.idx < self.len {
+            let elem = self.segvec.get(self.idx);
+            self.idx += 1;
+            if elem.is_some() {
+                return elem;
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.len - self.idx))
+    }
+}
+
+// === impl Slot ===
+
+impl<T> Default for Slot<T> {
+    fn default() -> Self {
+        Self {
+            published: AtomicBool::new(false),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+        }
+    }
+}
+
+impl<T> Drop for Slot<T> {
+    fn drop(&mut self) {
+        if *self.published.get_mut() {
+            // Safety: the slot's value was published, so it's initialized.
+            unsafe { self.value.get_mut().assume_init_drop() }
+        }
+    }
+}
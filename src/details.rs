@@ -0,0 +1,256 @@
+//! Inspecting the layout of a `SegVec`'s data blocks.
+//!
+//! See [`SegVec::debug_details`] for details.
+use crate::{Allocator, GrowthPolicy, SegVec};
+use core::{cmp, fmt};
+
+/// A view of a [`SegVec`]'s internal layout, for debugging.
+///
+/// This is returned by [`SegVec::debug_details`].
+///
+/// - The [`Display`](fmt::Display) implementation draws a diagram of the
+///   `SegVec`'s data blocks, grouped by superblock (if the growth policy has
+///   superblocks), with the number of elements in each data block. The data
+///   blocks skipped when the `SegVec` was created with a large first data
+///   block are summarized on one line, and the data block the next element
+///   will be pushed to is marked.
+/// - The [`Debug`](fmt::Debug) implementation prints the `SegVec`'s metadata
+///   and the elements in each data block.
+/// - When the `serde` feature flag is enabled, `DebugDetails` implements
+///   `Serialize`, as a machine-readable description of the layout which
+///   doesn't include the elements themselves.
+///
+/// The exact output of each format is not guaranteed to be stable.
+///
+/// # Examples
+///
+/// ```
+/// use segvec::SegVec;
+///
+/// let mut sv: SegVec<u32> = SegVec::with_capacity(4);
+/// sv.extend(0..10);
+///
+/// let diagram = sv.debug_details().to_string();
+/// assert_eq!(
+///     diagram,
+///     "SegVec (len 10, capacity 12, 3 data blocks)
+///   skipped 4 data blocks (7 indices)
+///   superblock 3:
+///     data block 0 [####] 4/4
+///     data block 1 [####] 4/4
+///   superblock 4:
+///     data block 2 [##..] 2/4 <- empty_data_block
+/// ",
+/// );
+/// ```
+pub struct DebugDetails<'segvec, T, A: Allocator, P: GrowthPolicy>(
+    pub(crate) &'segvec SegVec<T, A, P>,
+);
+
+/// The widest a data block is drawn by [`DebugDetails`]'s `Display`
+/// implementation. Larger data blocks are drawn to scale.
+const MAX_BAR_WIDTH: usize = 16;
+
+impl<T, A: Allocator, P: GrowthPolicy> SegVec<T, A, P> {
+    /// Returns a [`DebugDetails`] describing the `SegVec`'s internal layout,
+    /// which can be printed to see how its data blocks are laid out.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use segvec::SegVec;
+    ///
+    /// let sv: SegVec<u32> = SegVec::with_capacity(1000);
+    /// // The first data block holds 1024 elements, so every smaller data block
+    /// // in the layout is skipped.
+    /// println!("{}", sv.debug_details());
+    /// ```
+    pub fn debug_details(&self) -> DebugDetails<'_, T, A, P> {
+        DebugDetails(self)
+    }
+}
+
+impl<T, A: Allocator, P: GrowthPolicy> DebugDetails<'_, T, A, P> {
+    /// Returns the growth policy's number for the data block at position
+    /// `block` in the index.
+    fn layout_block(&self, block: usize) -> usize {
+        block + self.0.meta.skipped_blocks
+    }
+}
+
+impl<T, A: Allocator, P: GrowthPolicy> fmt::Display for DebugDetails<'_, T, A, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let segvec = self.0;
+        let blocks = segvec.index.len();
+        writeln!(
+            f,
+            "SegVec (len {}, capacity {}, {} data block{})",
+            segvec.len(),
+            segvec.capacity(),
+            blocks,
+            if blocks == 1 { "" } else { "s" },
+        )?;
+
+        if SegVec::<T, A, P>::IS_ZST {
+            return writeln!(f, "  zero-sized elements are not stored in data blocks");
+        }
+
+        if segvec.meta.skipped_blocks > 0 {
+            writeln!(
+                f,
+                "  skipped {} data blocks ({} indices)",
+                segvec.meta.skipped_blocks, segvec.meta.skipped_indices,
+            )?;
+        }
+
+        // Align the columns of the diagram.
+        let max_capacity = (0..blocks)
+            .map(|block| segvec.block_capacity(block))
+            .max()
+            .unwrap_or(0);
+        let max_width = cmp::min(max_capacity, MAX_BAR_WIDTH);
+        let block_digits = digits(blocks.saturating_sub(1));
+        let len_digits = digits(max_capacity);
+        let mut superblock = None;
+        for block in 0..blocks {
+            let mut indent = "  ";
+            if let Some(sb) = P::superblock(self.layout_block(block)) {
+                if superblock != Some(sb) {
+                    writeln!(f, "  superblock {}:", sb)?;
+                    superblock = Some(sb);
+                }
+                indent = "    ";
+            }
+
+            let capacity = segvec.block_capacity(block);
+            let len = segvec.block_len(block);
+            let width = cmp::min(capacity, MAX_BAR_WIDTH);
+            // Round the filled part of the bar up, so that a data block with
+            // any elements in it never looks empty.
+            let (filled, rem) = ((len * width) / capacity, (len * width) % capacity);
+            let filled = if rem > 0 { filled + 1 } else { filled };
+            write!(
+                f,
+                "{}data block {:<block_digits$} [",
+                indent,
+                block,
+                block_digits = block_digits
+            )?;
+            for _ in 0..filled {
+                f.write_str("#")?;
+            }
+            for _ in filled..width {
+                f.write_str(".")?;
+            }
+            write!(
+                f,
+                "]{:pad$} {:>len_digits$}/{}",
+                "",
+                len,
+                capacity,
+                pad = max_width - width,
+                len_digits = len_digits
+            )?;
+            if block == segvec.meta.empty_data_block {
+                f.write_str(" <- empty_data_block")?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<T: fmt::Debug, A: Allocator, P: GrowthPolicy> fmt::Debug for DebugDetails<'_, T, A, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut f = f.debug_struct("SegVec");
+        f.field("meta", &self.0.meta)
+            .field("capacity", &self.0.capacity)
+            .field(
+                "blocks",
+                &(0..self.0.index.len())
+                    .map(|block| self.0.block_slice(block))
+                    .collect::<alloc::vec::Vec<_>>(),
+            );
+        #[cfg(debug_assertions)]
+        {
+            f.field("is_initialized", &self.0.is_initialized);
+        }
+        f.finish()
+    }
+}
+
+/// Returns the number of decimal digits in `n`.
+fn digits(n: usize) -> usize {
+    let mut digits = 1;
+    let mut n = n / 10;
+    while n > 0 {
+        digits += 1;
+        n /= 10;
+    }
+    digits
+}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use super::DebugDetails;
+    use crate::{Allocator, GrowthPolicy};
+    use serde::ser::{Serialize, SerializeSeq, SerializeStruct, Serializer};
+
+    /// The data blocks in the index.
+    struct Blocks<'a, 'segvec, T, A: Allocator, P: GrowthPolicy>(
+        &'a DebugDetails<'segvec, T, A, P>,
+    );
+
+    /// A single data block.
+    struct Block {
+        layout_block: usize,
+        superblock: Option<usize>,
+        capacity: usize,
+        len: usize,
+    }
+
+    impl<T, A: Allocator, P: GrowthPolicy> Serialize for DebugDetails<'_, T, A, P> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let meta = &self.0.meta;
+            let mut state = serializer.serialize_struct("SegVec", 7)?;
+            state.serialize_field("len", &meta.len)?;
+            state.serialize_field("capacity", &self.0.capacity())?;
+            state.serialize_field("skipped_blocks", &meta.skipped_blocks)?;
+            state.serialize_field("skipped_indices", &meta.skipped_indices)?;
+            state.serialize_field("empty_data_block", &meta.empty_data_block)?;
+            state.serialize_field("zero_sized", &crate::SegVec::<T, A, P>::IS_ZST)?;
+            state.serialize_field("blocks", &Blocks(self))?;
+            state.end()
+        }
+    }
+
+    impl<T, A: Allocator, P: GrowthPolicy> Serialize for Blocks<'_, '_, T, A, P> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let segvec = (self.0).0;
+            let blocks = segvec.index.len();
+            let mut seq = serializer.serialize_seq(Some(blocks))?;
+            for block in 0..blocks {
+                let layout_block = self.0.layout_block(block);
+                seq.serialize_element(&Block {
+                    layout_block,
+                    superblock: P::superblock(layout_block),
+                    capacity: segvec.block_capacity(block),
+                    len: segvec.block_len(block),
+                })?;
+            }
+            seq.end()
+        }
+    }
+
+    impl Serialize for Block {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut state = serializer.serialize_struct("Block", 4)?;
+            state.serialize_field("layout_block", &self.layout_block)?;
+            state.serialize_field("superblock", &self.superblock)?;
+            state.serialize_field("capacity", &self.capacity)?;
+            state.serialize_field("len", &self.len)?;
+            state.end()
+        }
+    }
+}
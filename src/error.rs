@@ -0,0 +1,83 @@
+use core::{alloc::Layout, fmt};
+
+/// The error type for `try_reserve` methods.
+///
+/// This is returned by [`SegVec::try_reserve`], [`SegVec::try_reserve_exact`],
+/// [`SegVec::try_push`], and [`SegVec::try_with_capacity`].
+///
+/// [`SegVec::try_reserve`]: crate::SegVec::try_reserve
+/// [`SegVec::try_reserve_exact`]: crate::SegVec::try_reserve_exact
+/// [`SegVec::try_push`]: crate::SegVec::try_push
+/// [`SegVec::try_with_capacity`]: crate::SegVec::try_with_capacity
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TryReserveError {
+    kind: TryReserveErrorKind,
+}
+
+/// Details of the allocation that caused a [`TryReserveError`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TryReserveErrorKind {
+    /// The total capacity of the `SegVec` would exceed `isize::MAX` bytes, or
+    /// computing it overflowed.
+    CapacityOverflow,
+
+    /// The memory allocator returned an error.
+    AllocError {
+        /// The layout of the allocation request that failed.
+        layout: Layout,
+    },
+}
+
+impl TryReserveError {
+    pub(crate) fn capacity_overflow() -> Self {
+        Self {
+            kind: TryReserveErrorKind::CapacityOverflow,
+        }
+    }
+
+    pub(crate) fn alloc_error(layout: Layout) -> Self {
+        Self {
+            kind: TryReserveErrorKind::AllocError { layout },
+        }
+    }
+
+    /// Returns details about the allocation that caused this error.
+    pub fn kind(&self) -> TryReserveErrorKind {
+        self.kind.clone()
+    }
+
+    /// Handles this error the same way an infallible allocation would: by
+    /// panicking on capacity overflow, or by calling the global allocation
+    /// error handler.
+    #[cold]
+    #[track_caller]
+    pub(crate) fn handle(self) -> ! {
+        match self.kind {
+            TryReserveErrorKind::CapacityOverflow => panic!("capacity overflow"),
+            TryReserveErrorKind::AllocError { layout } => alloc::alloc::handle_alloc_error(layout),
+        }
+    }
+}
+
+impl From<TryReserveErrorKind> for TryReserveError {
+    fn from(kind: TryReserveErrorKind) -> Self {
+        Self { kind }
+    }
+}
+
+impl fmt::Display for TryReserveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("memory allocation failed")?;
+        match self.kind {
+            TryReserveErrorKind::CapacityOverflow => {
+                f.write_str(" because the computed capacity exceeded the SegVec's maximum")
+            }
+            TryReserveErrorKind::AllocError { .. } => {
+                f.write_str(" because the memory allocator returned an error")
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TryReserveError {}
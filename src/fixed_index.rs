@@ -0,0 +1,262 @@
+use crate::{allocator, location::Location, Global};
+use core::{
+    marker::PhantomData,
+    ptr::{self, NonNull},
+    sync::atomic::{AtomicPtr, Ordering::*},
+};
+
+/// A fixed-size, lazily-allocated index of data blocks, which can be shared
+/// between threads.
+///
+/// Unlike the index block of a [`SegVec`](crate::SegVec), this index never
+/// moves: it has one entry per superblock, each of which points to an array of
+/// pointers to that superblock's data blocks. Both the per-superblock arrays
+/// and the data blocks themselves are allocated the first time they're
+/// needed, so a slot can be allocated through a shared reference.
+///
+/// Data blocks are laid out exactly as in Brodnik et al.'s paper: superblock
+/// `k` contains `2^floor(k/2)` data blocks of `2^ceil(k/2)` slots each.
+pub(crate) struct FixedIndex<S> {
+    superblocks: [AtomicPtr<AtomicPtr<S>>; SUPERBLOCKS],
+    _slots: PhantomData<S>,
+}
+
+/// An iterator over the allocated slots in a [`FixedIndex`].
+pub(crate) struct Iter<'index, S> {
+    index: &'index FixedIndex<S>,
+    /// The location of the next slot to visit.
+    loc: Location,
+}
+
+/// The index of slot `usize::MAX - 1` is in superblock `usize::BITS - 1`.
+const SUPERBLOCKS: usize = usize::BITS as usize;
+
+impl<S> FixedIndex<S> {
+    // This is only used to initialize the array of superblocks; each use of
+    // the constant creates a new `AtomicPtr`, which is exactly what we want.
+    #[allow(clippy::declare_interior_mutable_const)]
+    const EMPTY: AtomicPtr<AtomicPtr<S>> = AtomicPtr::new(ptr::null_mut());
+
+    pub(crate) const fn new() -> Self {
+        Self {
+            superblocks: [Self::EMPTY; SUPERBLOCKS],
+            _slots: PhantomData,
+        }
+    }
+
+    /// Returns the slot at index `i`, if its data block has been allocated.
+    pub(crate) fn get(&self, i: usize) -> Option<&S> {
+        // Safety: the slot is initialized, and it is only accessed through
+        // shared references while the index is shared.
+        self.slot_ptr(i).map(|slot| unsafe { &*slot })
+    }
+
+    /// Returns the slot at index `i`, mutably, if its data block has been
+    /// allocated.
+    pub(crate) fn get_mut(&mut self, i: usize) -> Option<&mut S> {
+        // Safety: the slot is initialized, and we have exclusive access to the
+        // index, so we have exclusive access to every slot in it.
+        self.slot_ptr(i).map(|slot| unsafe { &mut *slot })
+    }
+
+    /// Returns an iterator over every slot in the data blocks which have been
+    /// allocated, along with their indices, in index order.
+    pub(crate) fn iter(&self) -> Iter<'_, S> {
+        Iter {
+            index: self,
+            loc: Location {
+                superblock: 0,
+                block: 0,
+                slot: 0,
+            },
+        }
+    }
+
+    /// Returns a pointer to the slot at index `i`, if its data block has been
+    /// allocated.
+    fn slot_ptr(&self, i: usize) -> Option<*mut S> {
+        let loc = Location::of(i);
+        let blocks = self.superblocks[loc.superblock].load(Acquire);
+        if blocks.is_null() {
+            return None;
+        }
+
+        // Safety: the superblock's array of blocks was allocated with
+        // `blocks_in(superblock)` entries, and `loc.block` is in bounds.
+        let block = unsafe { &*blocks.add(loc.block) }.load(Acquire);
+        if block.is_null() {
+            return None;
+        }
+
+        // Safety: the block was allocated with `block_len(superblock)` slots,
+        // and `loc.slot` is in bounds.
+        Some(unsafe { block.add(loc.slot) })
+    }
+}
+
+impl<S> FixedIndex<S> {
+    /// Installs an already-allocated data block as data block `data_block`
+    /// in Brodnik et al.'s layout, allocating its superblock's array of data
+    /// blocks if necessary.
+    ///
+    /// # Safety
+    ///
+    /// `block` must have been allocated by the global allocator, with the
+    /// layout of an array of as many `S`s as the data block holds, and every
+    /// one of those `S`s must be initialized. The index takes ownership of
+    /// the block.
+    pub(crate) unsafe fn adopt_block(&mut self, data_block: usize, block: NonNull<S>) {
+        let loc = Location::of_data_block(data_block);
+        let blocks = get_or_alloc_array(
+            &self.superblocks[loc.superblock],
+            Location::blocks_in(loc.superblock),
+            || AtomicPtr::new(ptr::null_mut()),
+        );
+        // Safety: the superblock's array of blocks was allocated with
+        // `blocks_in(superblock)` entries, and `loc.block` is in bounds.
+        let entry = unsafe { (*blocks.add(loc.block)).get_mut() };
+        debug_assert!(
+            entry.is_null(),
+            "data block {} already allocated",
+            data_block
+        );
+        *entry = block.as_ptr();
+    }
+}
+
+impl<S: Default> FixedIndex<S> {
+    /// Returns the slot at index `i`, allocating its data block (and its
+    /// superblock's array of data blocks) if they haven't been allocated yet.
+    ///
+    /// New slots are initialized with `S::default()`.
+    ///
+    /// # Panics
+    ///
+    /// If `i` is `usize::MAX`, or if allocating the data block fails.
+    pub(crate) fn get_or_alloc(&self, i: usize) -> &S {
+        let loc = Location::of(i);
+        let blocks = get_or_alloc_array(
+            &self.superblocks[loc.superblock],
+            Location::blocks_in(loc.superblock),
+            || AtomicPtr::new(ptr::null_mut()),
+        );
+        // Safety: the superblock's array of blocks was allocated with
+        // `blocks_in(superblock)` entries, and `loc.block` is in bounds.
+        let block = get_or_alloc_array(
+            unsafe { &*blocks.add(loc.block) },
+            Location::block_len(loc.superblock),
+            S::default,
+        );
+        // Safety: the block was allocated with `block_len(superblock)`
+        // initialized slots, and `loc.slot` is in bounds.
+        unsafe { &*block.add(loc.slot) }
+    }
+}
+
+impl<S> Drop for FixedIndex<S> {
+    fn drop(&mut self) {
+        for (superblock, blocks) in self.superblocks.iter_mut().enumerate() {
+            let blocks = *blocks.get_mut();
+            if blocks.is_null() {
+                continue;
+            }
+
+            let num_blocks = Location::blocks_in(superblock);
+            for block in 0..num_blocks {
+                // Safety: the superblock's array of blocks was allocated with
+                // `num_blocks` entries.
+                let block = unsafe { *(*blocks.add(block)).get_mut() };
+                if !block.is_null() {
+                    // Safety: we have exclusive access to the index, and the
+                    // block was allocated with `block_len` initialized slots.
+                    unsafe { free_array(block, Location::block_len(superblock)) };
+                }
+            }
+            // Safety: we have exclusive access to the index, and the array was
+            // allocated with `num_blocks` initialized entries.
+            unsafe { free_array(blocks, num_blocks) };
+        }
+    }
+}
+
+// === impl Iter ===
+
+impl<'index, S> Iterator for Iter<'index, S> {
+    type Item = (usize, &'index S);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let loc = &mut self.loc;
+        while loc.superblock < SUPERBLOCKS {
+            let blocks = self.index.superblocks[loc.superblock].load(Acquire);
+            if blocks.is_null() || loc.block == Location::blocks_in(loc.superblock) {
+                loc.superblock += 1;
+                loc.block = 0;
+                continue;
+            }
+
+            // Safety: the superblock's array of blocks was allocated with
+            // `blocks_in(superblock)` entries, and `loc.block` is in bounds.
+            let block = unsafe { &*blocks.add(loc.block) }.load(Acquire);
+            if block.is_null() || loc.slot == Location::block_len(loc.superblock) {
+                loc.block += 1;
+                loc.slot = 0;
+                continue;
+            }
+
+            let idx = Location::start_of(loc.superblock, loc.block) + loc.slot;
+            // Safety: the block was allocated with `block_len(superblock)`
+            // initialized slots, and `loc.slot` is in bounds.
+            let slot = unsafe { &*block.add(loc.slot) };
+            loc.slot += 1;
+            return Some((idx, slot));
+        }
+
+        None
+    }
+}
+
+/// Loads the array that `ptr` points to, allocating an array of `len`
+/// elements, initialized by `init`, if it's null.
+///
+/// This never blocks: if several threads find the array missing, each of
+/// them allocates one, and only the first to publish its array wins. The
+/// others free their arrays and use the winner's.
+fn get_or_alloc_array<E>(ptr: &AtomicPtr<E>, len: usize, init: impl Fn() -> E) -> *mut E {
+    let array = ptr.load(Acquire);
+    if !array.is_null() {
+        return array;
+    }
+
+    let new_array = match allocator::allocate::<E>(&Global, len) {
+        Ok(array) => array.as_ptr(),
+        Err(error) => error.handle(),
+    };
+    for i in 0..len {
+        // Safety: the array was just allocated with `len` elements.
+        unsafe { new_array.add(i).write(init()) };
+    }
+
+    match ptr.compare_exchange(ptr::null_mut(), new_array, AcqRel, Acquire) {
+        Ok(_) => new_array,
+        Err(array) => {
+            // Safety: nothing else has seen the new array.
+            unsafe { free_array(new_array, len) };
+            array
+        }
+    }
+}
+
+/// Drops the elements of an array allocated by [`get_or_alloc_array`], and
+/// frees it.
+///
+/// # Safety
+///
+/// `array` must have been allocated by [`get_or_alloc_array`] with `len`
+/// elements, and must not be used again.
+unsafe fn free_array<E>(array: *mut E, len: usize) {
+    // Safety: guaranteed by the caller.
+    unsafe {
+        ptr::drop_in_place(ptr::slice_from_raw_parts_mut(array, len));
+        allocator::deallocate(&Global, ptr::NonNull::new_unchecked(array), len);
+    }
+}
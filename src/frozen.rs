@@ -0,0 +1,177 @@
+//! An append-only segmented vector which can be pushed to through a shared
+//! reference.
+//!
+//! See [`FrozenSegVec`] for details.
+use crate::SegVec;
+use core::{cell::UnsafeCell, fmt, iter::FromIterator};
+
+/// An append-only [`SegVec`] which can be pushed to through a shared
+/// reference.
+///
+/// Because the elements of a `SegVec` never move when it grows, pushing an
+/// element never invalidates references to existing elements. A
+/// `FrozenSegVec` takes advantage of this to hand out references to its
+/// elements which remain valid while more elements are pushed, making it
+/// useful as an arena for data structures which reference each other, such
+/// as parse trees and symbol tables.
+///
+/// Elements can't be removed or mutated through a shared reference. A
+/// `FrozenSegVec` can't be shared between threads; see
+/// [`ConcurrentSegVec`](crate::ConcurrentSegVec) for that.
+///
+/// # Examples
+///
+/// ```
+/// use segvec::FrozenSegVec;
+///
+/// let strings = FrozenSegVec::new();
+/// let hello: &String = strings.push(String::from("hello"));
+/// for i in 0..100 {
+///     strings.push(i.to_string());
+/// }
+///
+/// // `hello` is still valid.
+/// assert_eq!(hello, "hello");
+/// assert_eq!(strings.get(42).map(String::as_str), Some("41"));
+/// ```
+pub struct FrozenSegVec<T> {
+    segvec: UnsafeCell<SegVec<T>>,
+}
+
+/// An iterator over the elements of a [`FrozenSegVec`].
+///
+/// This is returned by [`FrozenSegVec::iter`]. Elements pushed while the
+/// iterator is in use will also be visited.
+#[derive(Debug)]
+pub struct Iter<'segvec, T> {
+    segvec: &'segvec FrozenSegVec<T>,
+    idx: usize,
+}
+
+impl<T> FrozenSegVec<T> {
+    /// Constructs a new, empty `FrozenSegVec`.
+    ///
+    /// The `FrozenSegVec` will not allocate until elements are pushed onto it.
+    pub const fn new() -> Self {
+        Self {
+            segvec: UnsafeCell::new(SegVec::new()),
+        }
+    }
+
+    /// Appends an element to the back of the collection, returning a
+    /// reference to it.
+    ///
+    /// The returned reference remains valid as more elements are pushed.
+    ///
+    /// # Panics
+    ///
+    /// If the new capacity exceeds `isize::MAX` bytes.
+    pub fn push(&self, value: T) -> &T {
+        // Safety: a `FrozenSegVec` is `!Sync`, and pushing doesn't call any
+        // user code, so nothing else is accessing the `SegVec`. References
+        // to existing elements point into the data blocks, which are never
+        // moved or accessed mutably by `push`.
+        let segvec = unsafe { &mut *self.segvec.get() };
+        let idx = segvec.push(value);
+        let elem: *const T = &segvec[idx];
+        // Safety: the element will never be moved, mutated or dropped while
+        // `self` is borrowed.
+        unsafe { &*elem }
+    }
+
+    /// Returns a reference to the element at `idx`, or `None` if it is out of
+    /// bounds.
+    pub fn get(&self, idx: usize) -> Option<&T> {
+        self.segvec().get(idx)
+    }
+
+    /// Returns the number of elements in the `FrozenSegVec`.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.segvec().len()
+    }
+
+    /// Returns `true` if the `FrozenSegVec` contains no elements.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns an iterator over the elements in the `FrozenSegVec`.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            segvec: self,
+            idx: 0,
+        }
+    }
+
+    /// Returns a mutable reference to the underlying `SegVec`.
+    ///
+    /// This allows removing and mutating elements, which is only possible
+    /// when no references to the elements exist.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut SegVec<T> {
+        self.segvec.get_mut()
+    }
+
+    /// Consumes the `FrozenSegVec`, returning the underlying `SegVec`.
+    #[inline]
+    pub fn into_inner(self) -> SegVec<T> {
+        self.segvec.into_inner()
+    }
+
+    fn segvec(&self) -> &SegVec<T> {
+        // Safety: the `SegVec` is only accessed mutably within `push`, which
+        // never overlaps with this borrow, since a `FrozenSegVec` is `!Sync`.
+        unsafe { &*self.segvec.get() }
+    }
+}
+
+impl<T> Default for FrozenSegVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> From<SegVec<T>> for FrozenSegVec<T> {
+    fn from(segvec: SegVec<T>) -> Self {
+        Self {
+            segvec: UnsafeCell::new(segvec),
+        }
+    }
+}
+
+impl<T> FromIterator<T> for FrozenSegVec<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::from(iter.into_iter().collect::<SegVec<T>>())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for FrozenSegVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'segvec, T> IntoIterator for &'segvec FrozenSegVec<T> {
+    type IntoIter = Iter<'segvec, T>;
+    type Item = &'segvec T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+// === impl Iter ===
+
+impl<'segvec, T> Iterator for Iter<'segvec, T> {
+    type Item = &'segvec T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Look up each element by index, rather than iterating over the
+        // `SegVec`'s blocks, since pushing may reallocate the index block.
+        let elem = self.segvec.get(self.idx)?;
+        self.idx += 1;
+        Some(elem)
+    }
+}
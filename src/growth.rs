@@ -0,0 +1,217 @@
+//! Growth policies, which determine the sizes of a `SegVec`'s data blocks.
+//!
+//! A [`SegVec`](crate::SegVec) is parameterized by a [`GrowthPolicy`], which
+//! decides how large each of its data blocks is, and so how an index is
+//! mapped to a data block. Different policies make different tradeoffs
+//! between the amount of wasted capacity and the number of data blocks:
+//!
+//! | Policy             | Data block `b` holds   | Wasted capacity | Data blocks for `n` elements |
+//! |--------------------|------------------------|-----------------|------------------------------|
+//! | [`Brodnik`]        | `2^ceil(k/2)` elements | _O(sqrt(n))_    | _O(sqrt(n))_                 |
+//! | [`Doubling`]       | `2^b` elements         | _O(n)_          | _O(log(n))_                  |
+//! | [`FixedChunks<C>`] | `C` elements           | _O(1)_          | _O(n)_                       |
+//!
+//! ([`Brodnik`]'s data blocks are grouped into superblocks; `k` is the
+//! superblock containing data block `b`.)
+//!
+//! The default policy, [`Brodnik`], is the one from the paper this crate
+//! implements.
+//! Other policies are selected with [`SegVec::with_policy`]:
+//!
+//! ```
+//! use segvec::{FixedChunks, SegVec};
+//!
+//! let mut sv = SegVec::with_policy(FixedChunks::<64>);
+//! sv.extend(0..100);
+//! assert_eq!(sv.capacity(), 128);
+//! ```
+//!
+//! [`SegVec::with_policy`]: crate::SegVec::with_policy
+use crate::location::Location;
+use core::fmt;
+
+/// Determines the sizes of a [`SegVec`](crate::SegVec)'s data blocks.
+///
+/// A growth policy describes a layout of data blocks, which is indexed as
+/// though the first data block held the element at index 0. When a `SegVec`
+/// is created with a large first data block, the data blocks before it in the
+/// layout are skipped.
+///
+/// See the [module-level documentation](self) for the policies this crate
+/// provides.
+///
+/// # Safety
+///
+/// `SegVec` relies on the layout being consistent for memory safety. Data
+/// block `0` must start at index 0, every data block must hold at least one
+/// element, and each data block must start where the previous one ends.
+/// [`locate`](Self::locate) must return the data block containing an index,
+/// and the index's offset within that data block.
+pub unsafe trait GrowthPolicy {
+    /// Returns the number of elements data block `block` holds.
+    fn block_capacity(block: usize) -> usize;
+
+    /// Returns the index of the first element in data block `block`.
+    fn block_start(block: usize) -> usize;
+
+    /// Returns the data block containing the element at `idx`, and the
+    /// element's offset within that data block.
+    fn locate(idx: usize) -> (usize, usize);
+
+    /// Returns the data block a `SegVec` should start with, if its first data
+    /// block should hold `capacity` elements.
+    ///
+    /// `capacity` is always a power of two. Every data block before the
+    /// returned one is skipped.
+    fn first_block(capacity: usize) -> usize;
+
+    /// Returns the superblock containing data block `block`, if the layout
+    /// groups its data blocks into superblocks.
+    ///
+    /// This is only used for diagnostics: the superblock counts in
+    /// [`SegVecStats`](crate::SegVecStats), and the superblock boundaries in
+    /// [`DebugDetails`](crate::DebugDetails). By default, it returns `None`.
+    fn superblock(block: usize) -> Option<usize> {
+        let _ = block;
+        None
+    }
+}
+
+/// The growth policy from Brodnik et al.'s paper, _Resizable Arrays in
+/// Optimal Time and Space_.
+///
+/// Data blocks are grouped into superblocks: superblock `k` contains
+/// `2^floor(k/2)` data blocks of `2^ceil(k/2)` elements each. A `SegVec` with
+/// `n` elements has _O(sqrt(n))_ data blocks, and wastes _O(sqrt(n))_
+/// capacity.
+///
+/// This is the default growth policy.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Brodnik;
+
+/// A growth policy where each data block is twice as large as the last.
+///
+/// Data block `b` holds `2^b` elements. This needs only _O(log(n))_ data
+/// blocks, so locating an element is cheap, and iteration visits few data
+/// blocks, but up to half of the capacity may be wasted, as with a `Vec`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Doubling;
+
+/// A growth policy where every data block holds `CHUNK` elements.
+///
+/// `CHUNK` must be a power of two, so that locating an element is just a
+/// shift and a mask. At most `CHUNK - 1` elements of capacity are wasted, but
+/// a `SegVec` with `n` elements has `n / CHUNK` data blocks, so its index
+/// grows linearly.
+#[derive(Copy, Clone, Default, PartialEq, Eq)]
+pub struct FixedChunks<const CHUNK: usize>;
+
+// === impl Brodnik ===
+
+unsafe impl GrowthPolicy for Brodnik {
+    #[inline]
+    fn block_capacity(block: usize) -> usize {
+        Location::block_len(Location::of_data_block(block).superblock)
+    }
+
+    #[inline]
+    fn block_start(block: usize) -> usize {
+        let loc = Location::of_data_block(block);
+        Location::start_of(loc.superblock, loc.block)
+    }
+
+    #[inline]
+    fn locate(idx: usize) -> (usize, usize) {
+        let loc = Location::of(idx);
+        (loc.data_block(), loc.slot)
+    }
+
+    fn first_block(capacity: usize) -> usize {
+        debug_assert!(capacity.is_power_of_two());
+        let m = capacity.trailing_zeros() as usize;
+        if m == 0 {
+            return 0;
+        }
+
+        // The first data block with `2^m` elements is the first data block
+        // in superblock `SB[2m - 1]`.
+        Location {
+            superblock: 2 * m - 1,
+            block: 0,
+            slot: 0,
+        }
+        .data_block()
+    }
+
+    fn superblock(block: usize) -> Option<usize> {
+        Some(Location::of_data_block(block).superblock)
+    }
+}
+
+// === impl Doubling ===
+
+unsafe impl GrowthPolicy for Doubling {
+    #[inline]
+    fn block_capacity(block: usize) -> usize {
+        1 << block
+    }
+
+    #[inline]
+    fn block_start(block: usize) -> usize {
+        (1 << block) - 1
+    }
+
+    #[inline]
+    fn locate(idx: usize) -> (usize, usize) {
+        // Data block `b` holds the indices whose binary representation, plus
+        // one, has `b + 1` bits.
+        let r = idx.checked_add(1).expect("SegVec index overflow");
+        let block = (usize::BITS - 1 - r.leading_zeros()) as usize;
+        (block, r - (1 << block))
+    }
+
+    fn first_block(capacity: usize) -> usize {
+        debug_assert!(capacity.is_power_of_two());
+        capacity.trailing_zeros() as usize
+    }
+}
+
+// === impl FixedChunks ===
+
+impl<const CHUNK: usize> FixedChunks<CHUNK> {
+    const SHIFT: u32 = {
+        assert!(
+            CHUNK.is_power_of_two(),
+            "FixedChunks chunk size must be a power of two"
+        );
+        CHUNK.trailing_zeros()
+    };
+}
+
+unsafe impl<const CHUNK: usize> GrowthPolicy for FixedChunks<CHUNK> {
+    #[inline]
+    fn block_capacity(_: usize) -> usize {
+        1 << Self::SHIFT
+    }
+
+    #[inline]
+    fn block_start(block: usize) -> usize {
+        block << Self::SHIFT
+    }
+
+    #[inline]
+    fn locate(idx: usize) -> (usize, usize) {
+        (idx >> Self::SHIFT, idx & (CHUNK - 1))
+    }
+
+    fn first_block(_: usize) -> usize {
+        // Every data block is the same size, so skipping some wouldn't help.
+        0
+    }
+}
+
+impl<const CHUNK: usize> fmt::Debug for FixedChunks<CHUNK> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FixedChunks<{}>", CHUNK)
+    }
+}
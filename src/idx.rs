@@ -0,0 +1,418 @@
+//! Segmented vectors indexed by typed keys.
+//!
+//! An [`IndexSegVec<I, T>`] is a [`SegVec<T>`] which is indexed by `I`, rather
+//! than by `usize`. `I` is usually a newtype around an integer, defined using
+//! the [`define_index_type!`](crate::define_index_type) macro, so that
+//! indices into different collections can't be mixed up.
+//!
+//! See [`IndexSegVec`] for details.
+use crate::{Iter, IterMut, SegVec};
+use core::{
+    fmt,
+    hash::Hash,
+    iter::{Enumerate, FromIterator},
+    marker::PhantomData,
+    ops::{Index, IndexMut},
+};
+
+/// A type which can be used to index an [`IndexSegVec`].
+///
+/// This is usually implemented using the
+/// [`define_index_type!`](crate::define_index_type) macro.
+pub trait Idx: Copy + Eq + Hash + fmt::Debug + 'static {
+    /// Converts a `usize` into an index.
+    ///
+    /// # Panics
+    ///
+    /// If `idx` can't be represented by this index type.
+    fn from_usize(idx: usize) -> Self;
+
+    /// Converts this index into a `usize`.
+    fn index(self) -> usize;
+}
+
+impl Idx for usize {
+    #[inline]
+    fn from_usize(idx: usize) -> Self {
+        idx
+    }
+
+    #[inline]
+    fn index(self) -> usize {
+        self
+    }
+}
+
+/// Defines a newtype which implements [`Idx`].
+///
+/// The newtype wraps an unsigned integer type, and derives `Copy`, `Eq`,
+/// `Ord` and `Hash`. It also gets `from_raw` and `raw` methods, to convert to
+/// and from the wrapped integer, and a `Debug` implementation which prints
+/// the type's name along with the index.
+///
+/// Converting a `usize` which doesn't fit in the wrapped integer type panics.
+///
+/// # Examples
+///
+/// ```
+/// segvec::define_index_type! {
+///     /// Identifies a node in a graph.
+///     pub struct NodeId = u32;
+/// }
+///
+/// let mut nodes = segvec::IndexSegVec::<NodeId, &str>::new();
+/// let a: NodeId = nodes.push("a");
+/// let b = nodes.push("b");
+///
+/// assert_eq!(nodes[b], "b");
+/// assert_eq!(a.raw(), 0);
+/// assert_eq!(format!("{:?}", b), "NodeId(1)");
+/// ```
+#[macro_export]
+macro_rules! define_index_type {
+    ($(#[$attr:meta])* $vis:vis struct $name:ident = $raw:ty;) => {
+        $(#[$attr])*
+        #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        #[repr(transparent)]
+        $vis struct $name($raw);
+
+        impl $name {
+            /// Constructs an index from its raw value.
+            #[inline]
+            #[allow(dead_code)]
+            $vis const fn from_raw(raw: $raw) -> Self {
+                Self(raw)
+            }
+
+            /// Returns the raw value of this index.
+            #[inline]
+            #[allow(dead_code)]
+            $vis const fn raw(self) -> $raw {
+                self.0
+            }
+        }
+
+        impl $crate::Idx for $name {
+            #[inline]
+            fn from_usize(idx: usize) -> Self {
+                match <$raw as ::core::convert::TryFrom<usize>>::try_from(idx) {
+                    Ok(raw) => Self(raw),
+                    Err(_) => panic!(
+                        "index {} is out of range for {}",
+                        idx,
+                        stringify!($name)
+                    ),
+                }
+            }
+
+            #[inline]
+            fn index(self) -> usize {
+                self.0 as usize
+            }
+        }
+
+        impl ::core::fmt::Debug for $name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                write!(f, "{}({})", stringify!($name), self.0)
+            }
+        }
+    };
+}
+
+/// A [`SegVec`] which is indexed by a typed key.
+///
+/// An `IndexSegVec<I, T>` stores its elements in a `SegVec<T>`, but it's
+/// indexed by `I` rather than by `usize`: it implements `Index<I>`, [`push`]
+/// returns an `I`, and [`iter_enumerated`] yields `(I, &T)` pairs. This makes
+/// it a stable-address replacement for `index_vec`'s `IndexVec`.
+///
+/// [`push`]: Self::push
+/// [`iter_enumerated`]: Self::iter_enumerated
+///
+/// # Examples
+///
+/// ```
+/// use segvec::{define_index_type, IndexSegVec};
+///
+/// define_index_type! {
+///     struct SymbolId = u32;
+/// }
+///
+/// let mut symbols: IndexSegVec<SymbolId, &str> = IndexSegVec::new();
+/// let foo = symbols.push("foo");
+/// let bar = symbols.push("bar");
+///
+/// assert_eq!(symbols[foo], "foo");
+/// assert_eq!(symbols.next_idx(), SymbolId::from_raw(2));
+///
+/// let names: Vec<_> = symbols.iter_enumerated().collect();
+/// assert_eq!(names, [(foo, &"foo"), (bar, &"bar")]);
+/// ```
+pub struct IndexSegVec<I: Idx, T> {
+    raw: SegVec<T>,
+    _idx: PhantomData<fn(&I)>,
+}
+
+/// An iterator over the elements of an [`IndexSegVec`], along with their
+/// indices.
+///
+/// This is returned by [`IndexSegVec::iter_enumerated`].
+#[derive(Debug)]
+pub struct IterEnumerated<'segvec, I, T> {
+    inner: Enumerate<Iter<'segvec, T>>,
+    _idx: PhantomData<fn() -> I>,
+}
+
+/// An iterator over mutable references to the elements of an
+/// [`IndexSegVec`], along with their indices.
+///
+/// This is returned by [`IndexSegVec::iter_mut_enumerated`].
+#[derive(Debug)]
+pub struct IterMutEnumerated<'segvec, I, T> {
+    inner: Enumerate<IterMut<'segvec, T>>,
+    _idx: PhantomData<fn() -> I>,
+}
+
+impl<I: Idx, T> IndexSegVec<I, T> {
+    /// Constructs a new, empty `IndexSegVec`.
+    ///
+    /// The `IndexSegVec` will not allocate until elements are pushed onto it.
+    pub const fn new() -> Self {
+        Self::from_raw(SegVec::new())
+    }
+
+    /// Constructs a new, empty `IndexSegVec` with the specified capacity.
+    ///
+    /// See [`SegVec::with_capacity`] for details.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::from_raw(SegVec::with_capacity(capacity))
+    }
+
+    /// Wraps a `SegVec`, indexing it by `I`.
+    pub const fn from_raw(raw: SegVec<T>) -> Self {
+        Self {
+            raw,
+            _idx: PhantomData,
+        }
+    }
+
+    /// Returns a reference to the underlying `SegVec`.
+    #[inline]
+    pub fn as_raw(&self) -> &SegVec<T> {
+        &self.raw
+    }
+
+    /// Returns a mutable reference to the underlying `SegVec`.
+    #[inline]
+    pub fn as_raw_mut(&mut self) -> &mut SegVec<T> {
+        &mut self.raw
+    }
+
+    /// Consumes the `IndexSegVec`, returning the underlying `SegVec`.
+    #[inline]
+    pub fn into_raw(self) -> SegVec<T> {
+        self.raw
+    }
+
+    /// Returns the number of elements in the `IndexSegVec`.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.raw.len()
+    }
+
+    /// Returns `true` if the `IndexSegVec` contains no elements.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.raw.is_empty()
+    }
+
+    /// Reserves capacity for at least `additional` more elements.
+    ///
+    /// See [`SegVec::reserve`] for details.
+    pub fn reserve(&mut self, additional: usize) {
+        self.raw.reserve(additional)
+    }
+
+    /// Returns the index which the next element pushed will have.
+    ///
+    /// # Panics
+    ///
+    /// If the length can't be represented by `I`.
+    #[inline]
+    pub fn next_idx(&self) -> I {
+        I::from_usize(self.len())
+    }
+
+    /// Returns a reference to the element at `idx`, or `None` if it is out of
+    /// bounds.
+    pub fn get(&self, idx: I) -> Option<&T> {
+        self.raw.get(idx.index())
+    }
+
+    /// Returns a mutable reference to the element at `idx`, or `None` if it
+    /// is out of bounds.
+    pub fn get_mut(&mut self, idx: I) -> Option<&mut T> {
+        self.raw.get_mut(idx.index())
+    }
+
+    /// Appends an element to the back of the collection, returning its index.
+    ///
+    /// # Panics
+    ///
+    /// If the new element's index can't be represented by `I`, or if the new
+    /// capacity exceeds `isize::MAX` bytes.
+    pub fn push(&mut self, element: T) -> I {
+        // Check the index first, so that a panic doesn't leave behind an
+        // element which can't be indexed.
+        let idx = self.next_idx();
+        self.raw.push(element);
+        idx
+    }
+
+    /// Swaps the elements at indices `a` and `b`.
+    ///
+    /// # Panics
+    ///
+    /// If `a` or `b` is out of bounds.
+    #[track_caller]
+    pub fn swap(&mut self, a: I, b: I) {
+        self.raw.swap(a.index(), b.index())
+    }
+
+    /// Returns an iterator over the elements in the `IndexSegVec`.
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.raw.iter()
+    }
+
+    /// Returns an iterator over mutable references to the elements in the
+    /// `IndexSegVec`.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        self.raw.iter_mut()
+    }
+
+    /// Returns an iterator over the elements, along with their indices.
+    pub fn iter_enumerated(&self) -> IterEnumerated<'_, I, T> {
+        IterEnumerated {
+            inner: self.raw.iter().enumerate(),
+            _idx: PhantomData,
+        }
+    }
+
+    /// Returns an iterator over mutable references to the elements, along
+    /// with their indices.
+    pub fn iter_mut_enumerated(&mut self) -> IterMutEnumerated<'_, I, T> {
+        IterMutEnumerated {
+            inner: self.raw.iter_mut().enumerate(),
+            _idx: PhantomData,
+        }
+    }
+
+    /// Returns an iterator over the indices of the elements.
+    pub fn indices(&self) -> impl ExactSizeIterator<Item = I> {
+        (0..self.len()).map(I::from_usize)
+    }
+}
+
+impl<I: Idx, T> Default for IndexSegVec<I, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I: Idx, T> From<SegVec<T>> for IndexSegVec<I, T> {
+    fn from(raw: SegVec<T>) -> Self {
+        Self::from_raw(raw)
+    }
+}
+
+impl<I: Idx, T> Index<I> for IndexSegVec<I, T> {
+    type Output = T;
+
+    #[track_caller]
+    fn index(&self, idx: I) -> &T {
+        &self.raw[idx.index()]
+    }
+}
+
+impl<I: Idx, T> IndexMut<I> for IndexSegVec<I, T> {
+    #[track_caller]
+    fn index_mut(&mut self, idx: I) -> &mut T {
+        &mut self.raw[idx.index()]
+    }
+}
+
+impl<I: Idx, T: fmt::Debug> fmt::Debug for IndexSegVec<I, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<I: Idx, T> Extend<T> for IndexSegVec<I, T> {
+    fn extend<It: IntoIterator<Item = T>>(&mut self, iter: It) {
+        let iter = iter.into_iter();
+        self.reserve(crate::size_hint_capacity(&iter));
+        for element in iter {
+            self.push(element);
+        }
+    }
+}
+
+impl<I: Idx, T> FromIterator<T> for IndexSegVec<I, T> {
+    fn from_iter<It: IntoIterator<Item = T>>(iter: It) -> Self {
+        let mut this = Self::new();
+        this.extend(iter);
+        this
+    }
+}
+
+impl<'segvec, I: Idx, T> IntoIterator for &'segvec IndexSegVec<I, T> {
+    type IntoIter = Iter<'segvec, T>;
+    type Item = &'segvec T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'segvec, I: Idx, T> IntoIterator for &'segvec mut IndexSegVec<I, T> {
+    type IntoIter = IterMut<'segvec, T>;
+    type Item = &'segvec mut T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+// === impl IterEnumerated ===
+
+impl<'segvec, I: Idx, T> Iterator for IterEnumerated<'segvec, I, T> {
+    type Item = (I, &'segvec T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (idx, elem) = self.inner.next()?;
+        Some((I::from_usize(idx), elem))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<I: Idx, T> ExactSizeIterator for IterEnumerated<'_, I, T> {}
+
+// === impl IterMutEnumerated ===
+
+impl<'segvec, I: Idx, T> Iterator for IterMutEnumerated<'segvec, I, T> {
+    type Item = (I, &'segvec mut T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (idx, elem) = self.inner.next()?;
+        Some((I::from_usize(idx), elem))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<I: Idx, T> ExactSizeIterator for IterMutEnumerated<'_, I, T> {}
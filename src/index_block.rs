@@ -0,0 +1,126 @@
+use crate::{
+    allocator::{self, Allocator},
+    TryReserveError,
+};
+use core::{
+    cmp,
+    ops::{Deref, DerefMut},
+    ptr::{self, NonNull},
+    slice,
+};
+
+/// The "index block": a growable array of pointers to data blocks.
+///
+/// This is essentially a minimal `Vec<E, A>`. It only supports `Copy`
+/// entries, since the entries themselves don't own anything that the index
+/// block needs to drop.
+pub(crate) struct IndexBlock<E: Copy, A: Allocator> {
+    ptr: NonNull<E>,
+    capacity: usize,
+    len: usize,
+    alloc: A,
+}
+
+impl<E: Copy, A: Allocator> IndexBlock<E, A> {
+    // Minimum capacity of the index block, once it allocates.
+    const MIN_CAP: usize = 4;
+
+    pub(crate) const fn new_in(alloc: A) -> Self {
+        Self {
+            ptr: NonNull::dangling(),
+            capacity: 0,
+            len: 0,
+            alloc,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn allocator(&self) -> &A {
+        &self.alloc
+    }
+
+    /// Returns the number of entries the index block can hold without
+    /// reallocating.
+    #[inline]
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Reserves room for at least `additional` more entries.
+    ///
+    /// Like `Vec`, this grows the index block geometrically, so that pushing
+    /// entries is amortized _O(1)_.
+    pub(crate) fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let required = self
+            .len
+            .checked_add(additional)
+            .ok_or_else(TryReserveError::capacity_overflow)?;
+        if required <= self.capacity {
+            return Ok(());
+        }
+
+        let capacity = cmp::max(
+            cmp::max(self.capacity.saturating_mul(2), required),
+            Self::MIN_CAP,
+        );
+        let ptr = allocator::allocate::<E>(&self.alloc, capacity)?;
+        if self.capacity > 0 {
+            // Safety: the new allocation is at least `self.len` entries long,
+            // and doesn't overlap the old one. The old allocation was
+            // allocated by `self.alloc` with `self.capacity`.
+            unsafe {
+                ptr::copy_nonoverlapping(self.ptr.as_ptr(), ptr.as_ptr(), self.len);
+                allocator::deallocate(&self.alloc, self.ptr, self.capacity);
+            }
+        }
+
+        self.ptr = ptr;
+        self.capacity = capacity;
+        Ok(())
+    }
+
+    /// Appends an entry. There must already be room for it.
+    pub(crate) fn push(&mut self, entry: E) {
+        assert!(
+            self.len < self.capacity,
+            "index block must have room for a new entry"
+        );
+        // Safety: we just checked that the entry is in bounds of the
+        // allocation.
+        unsafe { self.ptr.as_ptr().add(self.len).write(entry) };
+        self.len += 1;
+    }
+
+    /// Shortens the index block, keeping the first `len` entries.
+    pub(crate) fn truncate(&mut self, len: usize) {
+        self.len = cmp::min(self.len, len);
+    }
+}
+
+impl<E: Copy, A: Allocator> Deref for IndexBlock<E, A> {
+    type Target = [E];
+
+    #[inline]
+    fn deref(&self) -> &[E] {
+        // Safety: the first `self.len` entries are initialized.
+        unsafe { slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<E: Copy, A: Allocator> DerefMut for IndexBlock<E, A> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut [E] {
+        // Safety: the first `self.len` entries are initialized.
+        unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<E: Copy, A: Allocator> Drop for IndexBlock<E, A> {
+    fn drop(&mut self) {
+        if self.capacity > 0 {
+            // Safety: `self.ptr` was allocated by `self.alloc` with
+            // `self.capacity`.
+            unsafe { allocator::deallocate(&self.alloc, self.ptr, self.capacity) }
+        }
+    }
+}
@@ -0,0 +1,271 @@
+//! Checking a `SegVec`'s internal invariants.
+//!
+//! See [`SegVec::check_invariants`] for details.
+use crate::{Allocator, GrowthPolicy, SegVec};
+use core::fmt;
+
+/// An internal invariant of a [`SegVec`] which doesn't hold.
+///
+/// This is returned by [`SegVec::check_invariants`]. If a `SegVec` ever
+/// returns one of these, it's a bug in `segvec` (or in a custom
+/// [`GrowthPolicy`]), and should be reported.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum InvariantViolation {
+    /// The `SegVec` holds more elements than its capacity.
+    LenExceedsCapacity {
+        /// The number of elements in the `SegVec`.
+        len: usize,
+        /// The `SegVec`'s capacity.
+        capacity: usize,
+    },
+
+    /// The `SegVec`'s capacity isn't the total capacity of its data blocks.
+    CapacityMismatch {
+        /// The `SegVec`'s capacity.
+        capacity: usize,
+        /// The total capacity of the `SegVec`'s data blocks.
+        ///
+        /// For a `SegVec` of zero-sized elements, this is `usize::MAX`.
+        expected: usize,
+    },
+
+    /// The number of skipped data blocks doesn't match the size of the first
+    /// data block.
+    ///
+    /// Every data block in the growth policy's layout before the first one
+    /// with the first data block's capacity must be skipped.
+    SkippedBlocksMismatch {
+        /// The number of skipped data blocks.
+        skipped_blocks: usize,
+        /// The capacity of the first data block.
+        first_block_capacity: usize,
+        /// The number of data blocks which should have been skipped.
+        expected: usize,
+    },
+
+    /// The number of skipped indices isn't the number of elements the skipped
+    /// data blocks would hold.
+    SkippedIndicesMismatch {
+        /// The number of skipped indices.
+        skipped_indices: usize,
+        /// The number of elements the skipped data blocks would hold.
+        expected: usize,
+    },
+
+    /// The first data block is larger than the largest first data block a
+    /// `SegVec` can index.
+    FirstBlockTooLarge {
+        /// The capacity of the first data block.
+        capacity: usize,
+        /// The largest allowed capacity of the first data block.
+        max: usize,
+    },
+
+    /// The current data block isn't the one containing the last element, so
+    /// there may be more than one partially-filled data block.
+    EmptyDataBlockMismatch {
+        /// The index of the current data block.
+        empty_data_block: usize,
+        /// The index of the data block containing the last element.
+        expected: usize,
+    },
+
+    /// A `SegVec` of zero-sized elements has data blocks in its index.
+    ZeroSizedDataBlocks {
+        /// The number of data blocks in the index.
+        blocks: usize,
+    },
+
+    /// The `SegVec`'s record of whether its first data block has been
+    /// allocated disagrees with its capacity.
+    ///
+    /// This is only checked when debug assertions are enabled.
+    InitializedMismatch {
+        /// Whether the `SegVec` believes its first data block is allocated.
+        is_initialized: bool,
+        /// The `SegVec`'s capacity.
+        capacity: usize,
+    },
+}
+
+impl<T, A: Allocator, P: GrowthPolicy> SegVec<T, A, P> {
+    /// Checks that the `SegVec`'s metadata is consistent with its index of
+    /// data blocks, returning the first [`InvariantViolation`] found.
+    ///
+    /// This checks that:
+    ///
+    /// - the length is at most the capacity,
+    /// - the capacity is the total capacity of the data blocks in the index,
+    /// - the skipped data blocks and indices match the first data block's
+    ///   capacity, and the first data block isn't too large,
+    /// - every data block before the current one is full, and every data block
+    ///   after it is empty, so at most one data block is partially filled.
+    ///
+    /// These invariants always hold for a `SegVec` used through its public
+    /// API, so this is intended for use in tests and fuzzers, which can call
+    /// it after every operation. It takes time proportional to the number of
+    /// data blocks.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`InvariantViolation`] if any invariant doesn't hold.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use segvec::SegVec;
+    ///
+    /// let mut sv: SegVec<i32> = SegVec::with_capacity(100);
+    /// sv.extend(0..1000);
+    /// assert_eq!(sv.check_invariants(), Ok(()));
+    /// ```
+    pub fn check_invariants(&self) -> Result<(), InvariantViolation> {
+        let len = self.len();
+        let capacity = self.capacity;
+        if len > capacity {
+            return Err(InvariantViolation::LenExceedsCapacity { len, capacity });
+        }
+
+        if Self::IS_ZST {
+            if !self.index.is_empty() {
+                return Err(InvariantViolation::ZeroSizedDataBlocks {
+                    blocks: self.index.len(),
+                });
+            }
+
+            if capacity != usize::MAX {
+                return Err(InvariantViolation::CapacityMismatch {
+                    capacity,
+                    expected: usize::MAX,
+                });
+            }
+
+            return Ok(());
+        }
+
+        #[cfg(debug_assertions)]
+        if self.is_initialized != (capacity != 0) {
+            return Err(InvariantViolation::InitializedMismatch {
+                is_initialized: self.is_initialized,
+                capacity,
+            });
+        }
+
+        let expected = (0..self.index.len())
+            .map(|block| self.block_capacity(block))
+            .try_fold(0usize, usize::checked_add)
+            .unwrap_or(usize::MAX);
+        if capacity != expected {
+            return Err(InvariantViolation::CapacityMismatch { capacity, expected });
+        }
+
+        let meta = &self.meta;
+        if !self.index.is_empty() {
+            let first_block_capacity = self.block_capacity(0);
+            if first_block_capacity > Self::MAX_FIRST_BLOCK_CAP {
+                return Err(InvariantViolation::FirstBlockTooLarge {
+                    capacity: first_block_capacity,
+                    max: Self::MAX_FIRST_BLOCK_CAP,
+                });
+            }
+
+            if first_block_capacity.is_power_of_two() {
+                let expected = P::first_block(first_block_capacity);
+                if meta.skipped_blocks != expected {
+                    return Err(InvariantViolation::SkippedBlocksMismatch {
+                        skipped_blocks: meta.skipped_blocks,
+                        first_block_capacity,
+                        expected,
+                    });
+                }
+            }
+        }
+
+        let expected = P::block_start(meta.skipped_blocks);
+        if meta.skipped_indices != expected {
+            return Err(InvariantViolation::SkippedIndicesMismatch {
+                skipped_indices: meta.skipped_indices,
+                expected,
+            });
+        }
+
+        // Each data block's length is derived from the `SegVec`'s length, so
+        // the data blocks are filled in order as long as the current data
+        // block is the one containing the last element.
+        let expected = match len.checked_sub(1) {
+            Some(last) => P::locate(last + meta.skipped_indices).0 - meta.skipped_blocks,
+            None => 0,
+        };
+        if meta.empty_data_block != expected {
+            return Err(InvariantViolation::EmptyDataBlockMismatch {
+                empty_data_block: meta.empty_data_block,
+                expected,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SegVec invariant violated: ")?;
+        match *self {
+            Self::LenExceedsCapacity { len, capacity } => {
+                write!(f, "the len ({}) exceeds the capacity ({})", len, capacity)
+            }
+            Self::CapacityMismatch { capacity, expected } => write!(
+                f,
+                "the capacity ({}) should be {}, the total capacity of the data blocks",
+                capacity, expected
+            ),
+            Self::SkippedBlocksMismatch {
+                skipped_blocks,
+                first_block_capacity,
+                expected,
+            } => write!(
+                f,
+                "{} data blocks were skipped, but a first data block of {} elements should skip {}",
+                skipped_blocks, first_block_capacity, expected
+            ),
+            Self::SkippedIndicesMismatch {
+                skipped_indices,
+                expected,
+            } => write!(
+                f,
+                "{} indices were skipped, but the skipped data blocks hold {}",
+                skipped_indices, expected
+            ),
+            Self::FirstBlockTooLarge { capacity, max } => write!(
+                f,
+                "the first data block holds {} elements, but at most {} are allowed",
+                capacity, max
+            ),
+            Self::EmptyDataBlockMismatch {
+                empty_data_block,
+                expected,
+            } => write!(
+                f,
+                "the current data block is {}, but the last element is in data block {}",
+                empty_data_block, expected
+            ),
+            Self::ZeroSizedDataBlocks { blocks } => write!(
+                f,
+                "a SegVec of zero-sized elements has {} data blocks",
+                blocks
+            ),
+            Self::InitializedMismatch {
+                is_initialized,
+                capacity,
+            } => write!(
+                f,
+                "is_initialized is {}, but the capacity is {}",
+                is_initialized, capacity
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InvariantViolation {}
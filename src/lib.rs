@@ -19,13 +19,42 @@
 //! - **...you want to slice the `Vec`**. Because a `SegVec` is _segmented_,
 //!   storing chunks of data at different non-contiguous memory locations, you
 //!   cannot slice a contiguous region of the vector. It is possible to
-//!   _iterate_ over ranges of a `SegVec`, but you cannot obtain a slice of data
-//!   in a `SegVec`. If you need to slice your vector, you can't use this.
-use std::{
-    cmp, fmt,
+//!   _iterate_ over ranges of a `SegVec` (using [`SegVec::slice`], which
+//!   returns a [`SegSlice`] view), but you cannot generally obtain a `&[T]`
+//!   slice of data in a `SegVec`. If you need to slice your vector, you can't
+//!   use this.
+//!
+//! ## Feature flags
+//!
+//! - **`std`** _(enabled by default)_: Enables integrations with the Rust
+//!   standard library, such as implementing `std::error::Error` for
+//!   [`TryReserveError`]. When this feature is disabled, `segvec` is
+//!   `#![no_std]`, and only depends on the `alloc` crate.
+//!
+//!   The vectors built on atomic compare-and-swap ([`ConcurrentSegVec`],
+//!   [`StableSegVec`], and [`SegVec::into_log`]) are only available on
+//!   targets which support it, so that the rest of the crate still builds for
+//!   targets like `thumbv6m-none-eabi`.
+//! - **`allocator-api2`**: Enables using `SegVec`s with custom memory
+//!   allocators. See the [`allocator`] module for details.
+//! - **`rayon`**: Implements parallel iterators for `SegVec`, using
+//!   [`rayon`](https://docs.rs/rayon). See the [`rayon`](mod@rayon) module
+//!   for details.
+//! - **`serde`**: Implements `serde`'s `Serialize` and `Deserialize` traits
+//!   for `SegVec`, and `Serialize` for [`DebugDetails`], as a machine-readable
+//!   description of a `SegVec`'s layout.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+use core::{
+    cmp::{self, Ordering},
+    fmt,
     iter::FromIterator,
+    marker::PhantomData,
     mem,
-    ops::{Index, IndexMut},
+    ops::{Bound, Index, IndexMut, RangeBounds},
+    ptr::{self, NonNull},
     slice,
 };
 
@@ -74,10 +103,60 @@ macro_rules! test_dbg {
     };
 }
 
+pub mod allocator;
+pub mod chunked;
+pub mod compact;
+#[cfg(target_has_atomic = "ptr")]
+pub mod concurrent;
+mod details;
+mod error;
+#[cfg(target_has_atomic = "ptr")]
+mod fixed_index;
+pub mod frozen;
+pub mod growth;
+pub mod idx;
+mod index_block;
+mod invariants;
+mod location;
+#[cfg(target_has_atomic = "ptr")]
+pub mod log;
+#[cfg(all(feature = "std", target_has_atomic = "ptr"))]
+pub mod once;
+#[cfg(feature = "rayon")]
+pub mod rayon;
+mod segslice;
+#[cfg(feature = "serde")]
+mod serde_impl;
+pub mod small;
+pub mod sparse;
+#[cfg(target_has_atomic = "ptr")]
+pub mod stable;
+mod stats;
+pub use allocator::{Allocator, Global};
+pub use chunked::ChunkedVec;
+pub use compact::SegVec32;
+#[cfg(target_has_atomic = "ptr")]
+pub use concurrent::ConcurrentSegVec;
+pub use details::DebugDetails;
+pub use error::{TryReserveError, TryReserveErrorKind};
+pub use frozen::FrozenSegVec;
+pub use growth::{Brodnik, Doubling, FixedChunks, GrowthPolicy};
+pub use idx::{Idx, IndexSegVec};
+use index_block::IndexBlock;
+pub use invariants::InvariantViolation;
+#[cfg(all(feature = "std", target_has_atomic = "ptr"))]
+pub use once::OnceSegVec;
+pub use segslice::{Chunks, SegSlice, Windows};
+pub use small::SmallSegVec;
+pub use sparse::SparseSegVec;
+#[cfg(target_has_atomic = "ptr")]
+pub use stable::StableSegVec;
+pub use stats::SegVecStats;
+
 #[cfg(test)]
 mod tests;
 
-pub struct SegVec<T> {
+pub struct SegVec<T, A: Allocator = Global, P: GrowthPolicy = Brodnik> {
     meta: Meta,
 
     /// The total capacity of the `SegVec`. This _includes_ used capacity.
@@ -85,346 +164,1548 @@ pub struct SegVec<T> {
     /// This should always be >= `self.len()`.
     capacity: usize,
 
-    /// The "index block". This holds pointers to the allocated data blocks.
-    index: Vec<Block<T>>,
+    /// The "index block". This holds pointers to the allocated data blocks,
+    /// and the allocator used to allocate both the data blocks and the index
+    /// block itself.
+    ///
+    /// Data blocks are bare pointers: the capacity of each data block is
+    /// determined by its position in the index, and its length by the length
+    /// of the `SegVec`, so neither is stored.
+    index: IndexBlock<NonNull<T>, A>,
+
+    /// The `SegVec` owns the elements in its data blocks.
+    _elements: PhantomData<T>,
+
+    /// The growth policy, which determines the capacity of each data block.
+    _policy: PhantomData<fn() -> P>,
 
     #[cfg(debug_assertions)]
     is_initialized: bool,
 }
 
 #[derive(Debug)]
-pub struct Iter<'segvec, T> {
+pub struct Iter<'segvec, T, P: GrowthPolicy = Brodnik> {
     len: usize,
-    blocks: slice::Iter<'segvec, Block<T>>,
+    blocks: slice::Iter<'segvec, NonNull<T>>,
+    /// The growth policy's number for the next data block in `blocks`,
+    /// including any skipped blocks.
+    next_block: usize,
     curr_block: slice::Iter<'segvec, T>,
+    _policy: PhantomData<fn() -> P>,
 }
 
 #[derive(Debug)]
-pub struct IterMut<'segvec, T> {
+pub struct IterMut<'segvec, T, P: GrowthPolicy = Brodnik> {
     len: usize,
-    blocks: slice::IterMut<'segvec, Block<T>>,
+    blocks: slice::Iter<'segvec, NonNull<T>>,
+    /// The growth policy's number for the next data block in `blocks`,
+    /// including any skipped blocks.
+    next_block: usize,
     curr_block: slice::IterMut<'segvec, T>,
+    _policy: PhantomData<fn() -> P>,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 struct Meta {
     /// The total number of elements in this `SegVec`.
     ///
     /// This is denoted by _n_ in the paper.
     len: usize,
 
-    /// Current superblock index.
-    superblock: usize,
-
-    /// The capacity of the current superblock.
+    /// The number of data blocks in the growth policy's layout which precede
+    /// the first data block in the index.
     ///
-    /// When the superblock has `sb_cap` blocks in it, allocating a new block
-    /// will increment the superblock index and reset `sb_cap`.
-    sb_cap: usize,
-
-    /// The current number of blocks in the current superblock.
-    sb_len: usize,
-
-    /// The capacity of the blocks in the current superblock.
-    block_cap: usize,
-
+    /// These are skipped when a `SegVec` is created with a large first data
+    /// block.
     skipped_blocks: usize,
+
+    /// The number of elements the skipped data blocks would hold.
     skipped_indices: usize,
 
     /// The current empty data block to push in.
     empty_data_block: usize,
-}
 
-struct Block<T> {
-    elements: Vec<T>,
+    /// The indices held by data block `empty_data_block`, once `push` has
+    /// located it, so that later pushes into the same data block needn't
+    /// locate the new element or check the capacity.
+    ///
+    /// This is `0..0` until `push` locates an element, and is reset whenever
+    /// the length changes any other way.
+    push_start: usize,
+    push_end: usize,
 }
 
-/// TODO(eliza): consider making this an API?
-#[cfg(test)]
-struct DebugDetails<'segvec, T>(&'segvec SegVec<T>);
+impl<T, P: GrowthPolicy> SegVec<T, Global, P> {
+    /// Constructs a new, empty `SegVec` which lays out its data blocks
+    /// according to `policy`.
+    ///
+    /// The `SegVec` will not allocate until elements are pushed onto it. See
+    /// the [`growth`] module for the available policies.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use segvec::{Doubling, SegVec};
+    ///
+    /// let mut sv = SegVec::with_policy(Doubling);
+    /// sv.reserve_exact(64);
+    /// assert_eq!(sv.capacity(), 64);
+    ///
+    /// // The next data block is twice as large as the first.
+    /// sv.extend(0..65);
+    /// assert_eq!(sv.capacity(), 64 + 128);
+    /// assert_eq!(sv[42], 42);
+    /// ```
+    pub fn with_policy(policy: P) -> Self {
+        Self::with_policy_in(policy, Global)
+    }
+}
 
 impl<T> SegVec<T> {
     pub const fn new() -> Self {
+        Self::new_in(Global)
+    }
+
+    /// Constructs a new, empty `SegVec` with at least the specified capacity.
+    ///
+    /// This is equivalent to calling [`reserve`] on a new `SegVec`. See the
+    /// documentation for [`reserve`] for details on the resulting capacity.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity exceeds `isize::MAX` bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use segvec::SegVec;
+    ///
+    /// let sv: SegVec<i32> = SegVec::with_capacity(10);
+    /// assert_eq!(sv.capacity(), 16);
+    /// ```
+    ///
+    /// [`reserve`]: SegVec::reserve
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_in(capacity, Global)
+    }
+
+    /// Constructs a new, empty `SegVec` whose first data block holds exactly
+    /// `capacity` elements.
+    ///
+    /// The returned `SegVec`'s [`capacity`] is exactly `capacity`. Once the
+    /// first data block is full, the `SegVec` grows by adding data blocks of
+    /// the same size, until it has as many data blocks of that size as the
+    /// paper's layout would, and then continues growing normally.
+    ///
+    /// If `T` is zero-sized, nothing is allocated, and the capacity is
+    /// `usize::MAX`, as for any `SegVec` of zero-sized elements.
+    ///
+    /// # Panics
+    ///
+    /// - If `capacity` is not a power of two.
+    /// - If `capacity` is greater than `2^(usize::BITS / 2)` elements, since
+    ///   the indices of the skipped lower-size data blocks would overflow a
+    ///   `usize`.
+    /// - If `capacity` elements exceed `isize::MAX` bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use segvec::SegVec;
+    ///
+    /// let mut sv: SegVec<i32> = SegVec::with_first_block(64);
+    /// assert_eq!(sv.capacity(), 64);
+    ///
+    /// sv.extend(0..65);
+    /// assert_eq!(sv.capacity(), 128);
+    /// ```
+    ///
+    /// [`capacity`]: SegVec::capacity
+    #[track_caller]
+    pub fn with_first_block(capacity: usize) -> Self {
+        Self::with_first_block_in(capacity, Global)
+    }
+
+    /// Constructs a new, empty `SegVec` with at least the specified capacity,
+    /// returning an error if the capacity overflows or the allocator reports
+    /// a failure.
+    ///
+    /// # Errors
+    ///
+    /// If the capacity overflows `isize::MAX` _bytes_, or if the allocator
+    /// reports a failure, then an error is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use segvec::SegVec;
+    ///
+    /// let sv: SegVec<i32> = SegVec::try_with_capacity(10).expect("allocation failed");
+    /// assert!(sv.capacity() >= 10);
+    ///
+    /// assert!(SegVec::<u64>::try_with_capacity(usize::MAX).is_err());
+    /// ```
+    pub fn try_with_capacity(capacity: usize) -> Result<Self, TryReserveError> {
+        Self::try_with_capacity_in(capacity, Global)
+    }
+}
+
+impl<T, A: Allocator> SegVec<T, A> {
+    /// Constructs a new, empty `SegVec` which will allocate memory using
+    /// `alloc`.
+    ///
+    /// The `SegVec` will not allocate until elements are pushed onto it.
+    pub const fn new_in(alloc: A) -> Self {
+        Self::empty_in(alloc)
+    }
+
+    /// Constructs a new, empty `SegVec` with at least the specified capacity,
+    /// which will allocate memory using `alloc`.
+    ///
+    /// See [`SegVec::with_capacity`] for details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity exceeds `isize::MAX` bytes.
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        let mut this = Self::new_in(alloc);
+        this.reserve(capacity);
+        this
+    }
+
+    /// Constructs a new, empty `SegVec` whose first data block holds exactly
+    /// `capacity` elements, which will allocate memory using `alloc`.
+    ///
+    /// See [`SegVec::with_first_block`] for details.
+    ///
+    /// # Panics
+    ///
+    /// - If `capacity` is not a power of two.
+    /// - If `capacity` is greater than `2^(usize::BITS / 2)` elements.
+    /// - If `capacity` elements exceed `isize::MAX` bytes.
+    #[track_caller]
+    pub fn with_first_block_in(capacity: usize, alloc: A) -> Self {
+        assert!(
+            capacity.is_power_of_two(),
+            "SegVec first block capacity must be a power of two (got {})",
+            capacity
+        );
+        assert!(
+            capacity <= Self::MAX_FIRST_BLOCK_CAP,
+            "SegVec first block capacity must be at most {} (got {})",
+            Self::MAX_FIRST_BLOCK_CAP,
+            capacity
+        );
+
+        let mut this = Self::new_in(alloc);
+        if Self::IS_ZST {
+            return this;
+        }
+
+        if let Err(error) = this.try_initialize(capacity) {
+            error.handle();
+        }
+        this
+    }
+
+    /// Constructs a new, empty `SegVec` with at least the specified capacity,
+    /// which will allocate memory using `alloc`, returning an error if the
+    /// capacity overflows or the allocator reports a failure.
+    ///
+    /// See [`SegVec::try_with_capacity`] for details.
+    ///
+    /// # Errors
+    ///
+    /// If the capacity overflows `isize::MAX` _bytes_, or if the allocator
+    /// reports a failure, then an error is returned.
+    pub fn try_with_capacity_in(capacity: usize, alloc: A) -> Result<Self, TryReserveError> {
+        let mut this = Self::new_in(alloc);
+        this.try_reserve(capacity)?;
+        Ok(this)
+    }
+}
+
+impl<T, A: Allocator, P: GrowthPolicy> SegVec<T, A, P> {
+    // Minimum size of the first data block `Vec`.
+    // This is what `std` will allocate initially if a `Vec` is constructed
+    // without using `with_capacity`.
+    // Copied from https://github.com/rust-lang/rust/blob/996ff2e0a0f911f52bb1de6bdd0cfd5704de1fc9/library/alloc/src/raw_vec.rs#L117-L128
+    const MIN_NON_ZERO_CAP: usize = if Self::IS_ZST {
+        // Zero-sized elements don't need data blocks at all.
+        usize::MAX
+    } else if mem::size_of::<T>() == 1 {
+        8
+    } else if mem::size_of::<T>() <= 1024 {
+        4
+    } else {
+        1
+    };
+
+    // Maximum size of the first data block.
+    // When a `SegVec` is created with a large initial capacity, the low-size
+    // data blocks prior to the first one are skipped, but `locate` must still
+    // be able to count the skipped indices. If the first data block has
+    // `2^m` elements, there are `2^(2m - 1) - 1` skipped indices in the
+    // `Brodnik` layout, so this is the largest first block for which that
+    // doesn't overflow a `usize`. The other growth policies skip fewer
+    // indices, but share the same limit.
+    const MAX_FIRST_BLOCK_CAP: usize = 1 << (usize::BITS / 2);
+
+    // Whether `T` is a zero-sized type.
+    // A `SegVec` of zero-sized elements never allocates: it only tracks its
+    // length, and behaves as though it has a single data block at a dangling
+    // address, which holds `usize::MAX` elements.
+    const IS_ZST: bool = mem::size_of::<T>() == 0;
+
+    /// Constructs a new, empty `SegVec` which lays out its data blocks
+    /// according to `policy`, and will allocate memory using `alloc`.
+    ///
+    /// See [`SegVec::with_policy`] for details.
+    pub fn with_policy_in(policy: P, alloc: A) -> Self {
+        let _ = policy;
+        Self::empty_in(alloc)
+    }
+
+    const fn empty_in(alloc: A) -> Self {
         Self {
             meta: Meta::empty(),
-            index: Vec::new(),
-            capacity: 0,
+            index: IndexBlock::new_in(alloc),
+            capacity: if Self::IS_ZST { usize::MAX } else { 0 },
+            _elements: PhantomData,
+            _policy: PhantomData,
             #[cfg(debug_assertions)]
             is_initialized: false,
         }
     }
 
-    // Minimum size of the first data block `Vec`.
-    // This is what `std` will allocate initially if a `Vec` is constructed
-    // without using `with_capacity`.
-    // Copied from https://github.com/rust-lang/rust/blob/996ff2e0a0f911f52bb1de6bdd0cfd5704de1fc9/library/alloc/src/raw_vec.rs#L117-L128
-    const MIN_NON_ZERO_CAP: usize = if mem::size_of::<T>() == 1 {
-        8
-    } else if mem::size_of::<T>() <= 1024 {
-        4
-    } else {
-        1
-    };
+    /// Returns a reference to the underlying allocator.
+    #[inline]
+    pub fn allocator(&self) -> &A {
+        self.index.allocator()
+    }
+
+    /// Returns the number of elements the `SegVec` can hold without
+    /// reallocating.
+    ///
+    /// A `SegVec` of zero-sized elements never allocates, so its capacity is
+    /// always `usize::MAX`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use segvec::SegVec;
+    ///
+    /// let sv: SegVec<i32> = SegVec::with_capacity(10);
+    /// assert!(sv.capacity() >= 10);
+    /// ```
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of elements in the `SegVec`, also referred to
+    /// as its 'length'.
+    ///
+    /// # Examples
+    ///
+    // TODO(eliza): fix this example.
+    /// ```ignore
+    /// use segvec::segvec;
+    ///
+    /// let sv = segvec![1, 2, 3];
+    /// assert_eq!(a.len(), 3);
+    /// ```
+    #[doc(alias = "length")]
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.meta.len
+    }
+
+    /// Reserves capacity for at least `additional` more elements to be inserted
+    /// in the given `SegVec<T>`. The collection may reserve more space to avoid
+    /// frequent reallocations. After calling `reserve`, capacity will be
+    /// greater than or equal to `self.len() + additional`. Does nothing if
+    /// capacity is already sufficient.
+    ///
+    /// Existing data blocks are never reallocated. Instead:
+    ///
+    /// - If the `SegVec` has not allocated yet, its first data block will hold
+    ///   `additional` elements, rounded up to the next power of two, and then
+    ///   up to a minimum block size for `T` (8 elements if `T` is 1 byte, 4 if
+    ///   it is at most 1 KiB, and 1 otherwise). All lower-size data blocks are
+    ///   skipped. The first data block holds at most `2^(usize::BITS / 2)`
+    ///   elements; if more capacity is requested, more data blocks are added as
+    ///   described below.
+    /// - Otherwise, the next data blocks in the layout are added, one at a
+    ///   time, until there is enough capacity. The new capacity exceeds
+    ///   `self.len() + additional` by less than the size of the last data block
+    ///   added, which is _O(sqrt(n))_ with the default [`GrowthPolicy`].
+    ///
+    /// If the growth policy has no data block of the requested size, the
+    /// first data block is the first one in its layout instead.
+    ///
+    /// [`reserve_exact`] doesn't round up the first data block to the minimum
+    /// block size for `T`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity exceeds `isize::MAX` bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use segvec::SegVec;
+    ///
+    /// let mut sv: SegVec<i32> = SegVec::with_capacity(1);
+    /// sv.reserve(10);
+    /// assert!(sv.capacity() >= 11);
+    /// ```
+    ///
+    /// [`reserve_exact`]: SegVec::reserve_exact
+    pub fn reserve(&mut self, additional: usize) {
+        if let Err(error) = self.try_reserve(additional) {
+            error.handle();
+        }
+    }
+
+    /// Reserves the minimum capacity for at least `additional` more elements
+    /// to be inserted in the given `SegVec<T>`. After calling `reserve_exact`,
+    /// capacity will be greater than or equal to `self.len() + additional`.
+    /// Does nothing if capacity is already sufficient.
+    ///
+    /// This behaves like [`reserve`], except that if the `SegVec` has not
+    /// allocated yet, the size of the first data block is *not* rounded up to
+    /// a minimum block size for `T`. Its size will be exactly `additional`,
+    /// rounded up to the next power of two (and capped at
+    /// `2^(usize::BITS / 2)` elements, as described for [`reserve`]).
+    ///
+    /// Note that data blocks must have power-of-two sizes, and existing data
+    /// blocks are never reallocated, so the `SegVec` may still have more
+    /// capacity than requested.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity exceeds `isize::MAX` bytes.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use segvec::SegVec;
+    ///
+    /// let mut sv: SegVec<u8> = SegVec::new();
+    /// sv.reserve_exact(5);
+    /// assert_eq!(sv.capacity(), 8);
+    ///
+    /// let mut sv: SegVec<u8> = SegVec::new();
+    /// sv.reserve_exact(2);
+    /// assert_eq!(sv.capacity(), 2);
+    ///
+    /// // `reserve` would have used the minimum block size for `u8` instead.
+    /// let mut sv: SegVec<u8> = SegVec::new();
+    /// sv.reserve(2);
+    /// assert_eq!(sv.capacity(), 8);
+    /// ```
+    ///
+    /// [`reserve`]: SegVec::reserve
+    pub fn reserve_exact(&mut self, additional: usize) {
+        if let Err(error) = self.try_reserve_exact(additional) {
+            error.handle();
+        }
+    }
+
+    /// Tries to reserve capacity for at least `additional` more elements to be
+    /// inserted in the given `SegVec<T>`. The collection may reserve more space
+    /// to avoid frequent reallocations. After calling `try_reserve`, capacity
+    /// will be greater than or equal to `self.len() + additional` if it returns
+    /// `Ok(())`. Does nothing if capacity is already sufficient.
+    ///
+    /// If this returns an error, the `SegVec` is left unchanged.
+    ///
+    /// # Errors
+    ///
+    /// If the capacity overflows `isize::MAX` _bytes_, or if the allocator
+    /// reports a failure, then an error is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use segvec::{SegVec, TryReserveErrorKind};
+    ///
+    /// let mut sv: SegVec<u64> = SegVec::new();
+    /// sv.try_reserve(10).expect("allocation failed");
+    /// assert!(sv.capacity() >= 10);
+    ///
+    /// let error = sv.try_reserve(usize::MAX).unwrap_err();
+    /// assert_eq!(error.kind(), TryReserveErrorKind::CapacityOverflow);
+    /// ```
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.try_reserve_inner(additional, Self::MIN_NON_ZERO_CAP)
+    }
+
+    /// Tries to reserve the minimum capacity for at least `additional` more
+    /// elements to be inserted in the given `SegVec<T>`.
+    ///
+    /// This is the fallible version of [`reserve_exact`]; see its
+    /// documentation for details on the resulting capacity.
+    ///
+    /// If this returns an error, the `SegVec` is left unchanged.
+    ///
+    /// # Errors
+    ///
+    /// If the capacity overflows `isize::MAX` _bytes_, or if the allocator
+    /// reports a failure, then an error is returned.
+    ///
+    /// [`reserve_exact`]: SegVec::reserve_exact
+    pub fn try_reserve_exact(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        self.try_reserve_inner(additional, 1)
+    }
+
+    fn try_reserve_inner(
+        &mut self,
+        additional: usize,
+        min_cap: usize,
+    ) -> Result<(), TryReserveError> {
+        if additional == 0 {
+            return Ok(());
+        }
+
+        // The total capacity of the `SegVec` may not exceed `isize::MAX`
+        // *bytes*, even though each individual data block is much smaller.
+        let required = self
+            .len()
+            .checked_add(additional)
+            .ok_or_else(TryReserveError::capacity_overflow)?;
+        required
+            .checked_mul(mem::size_of::<T>())
+            .filter(|&bytes| bytes <= isize::MAX as usize)
+            .ok_or_else(TryReserveError::capacity_overflow)?;
+
+        if Self::IS_ZST {
+            // There's always room for more zero-sized elements, as long as the
+            // length doesn't overflow.
+            return Ok(());
+        }
+
+        if self.capacity == 0 {
+            // If the requested capacity is not a power of two, round up to the next
+            // power of two.
+            test_dbg!(let capacity = additional
+                .checked_next_power_of_two()
+                .ok_or_else(TryReserveError::capacity_overflow)?;);
+
+            // If the capacity is less than the reasonable minimum capacity for the
+            // size of elements in the `SegVec`, use that capacity instead.
+            test_dbg!(let capacity = cmp::max(capacity, min_cap););
+
+            // If the capacity is more than the largest first data block we can
+            // index, use that instead and add more blocks below.
+            test_dbg!(let capacity = cmp::min(capacity, Self::MAX_FIRST_BLOCK_CAP););
+            self.try_initialize(capacity)?;
+
+            // If we couldn't allocate the rest of the requested capacity, go
+            // back to being empty, so that the `SegVec` is unchanged.
+            let result = self.try_grow(additional);
+            if result.is_err() {
+                self.dealloc_blocks(0);
+                self.meta = Meta::empty();
+                self.capacity = 0;
+                #[cfg(debug_assertions)]
+                {
+                    self.is_initialized = false;
+                }
+            }
+            return result;
+        }
+
+        #[cfg(debug_assertions)]
+        debug_assert!(self.is_initialized);
+
+        self.try_grow(additional)
+    }
+
+    fn locate(&self, i: usize) -> (usize, usize) {
+        if Self::IS_ZST {
+            return (0, i);
+        }
+
+        // The growth policy's layout starts at the first skipped block, so
+        // compensate for the skipped low-size blocks.
+        test_dbg!(let r = i + self.meta.skipped_indices;);
+        let (block, e) = P::locate(r);
+        test_dbg!(let data_block = block - self.meta.skipped_blocks;);
+
+        // If the data block index is out of bounds, panic with a nicer
+        // assertion with more debugging information.
+        debug_assert!(
+            data_block < self.index.len(),
+            "assertion failed: data_block < self.index.len(); \
+            data_block={}; self.index.len()={}; e={}; metadata={:#?}",
+            data_block,
+            self.index.len(),
+            e,
+            self.meta,
+        );
+
+        (data_block, test_dbg!(e))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.meta.len == 0
+    }
+
+    pub fn get(&self, idx: usize) -> Option<&T> {
+        if idx >= self.meta.len {
+            return None;
+        }
+
+        // Safety: the element is in bounds, so it's initialized.
+        Some(unsafe { &*self.elem_ptr(idx) })
+    }
+
+    pub fn get_mut(&mut self, idx: usize) -> Option<&mut T> {
+        if idx >= self.meta.len {
+            return None;
+        }
+
+        // Safety: the element is in bounds, so it's initialized, and we have
+        // exclusive access to it.
+        Some(unsafe { &mut *self.elem_ptr(idx) })
+    }
+
+    pub fn push(&mut self, element: T) -> usize {
+        // There's always room in the current data block, until the push
+        // which crosses into the next one.
+        if self.meta.len >= self.meta.push_end {
+            self.reserve(1);
+        }
+        self.push_within_capacity(element)
+    }
+
+    /// Appends an element to the back of the `SegVec`, returning its index,
+    /// or an error if a new data block could not be allocated.
+    ///
+    /// If this returns an error, `element` is dropped and the `SegVec` is left
+    /// unchanged.
+    ///
+    /// # Errors
+    ///
+    /// If the capacity overflows `isize::MAX` _bytes_, or if the allocator
+    /// reports a failure, then an error is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use segvec::SegVec;
+    ///
+    /// let mut sv = SegVec::new();
+    /// assert_eq!(sv.try_push(1), Ok(0));
+    /// assert_eq!(sv.try_push(2), Ok(1));
+    /// assert_eq!(sv[1], 2);
+    /// ```
+    pub fn try_push(&mut self, element: T) -> Result<usize, TryReserveError> {
+        if self.meta.len >= self.meta.push_end {
+            self.try_reserve(1)?;
+        }
+        Ok(self.push_within_capacity(element))
+    }
+
+    /// Appends an element to the back of the `SegVec`, which must already
+    /// have room for it.
+    fn push_within_capacity(&mut self, element: T) -> usize {
+        debug_assert!(self.capacity() > self.len());
+
+        // The new element may be in the data block after the current one, if
+        // the current one is full. There may be additional free blocks after
+        // that, either because `reserve` was just called by `push`, or due to
+        // previous `reserve`/`with_capacity` calls.
+        // NOTE: the Brodnik et al paper doesn't consider that you might
+        //       want to reserve capacity, so this is one of our deviations
+        //       from their algorithm.
+        let len = self.meta.len;
+        if len < self.meta.push_end {
+            // Safety: data block `empty_data_block` holds the indices
+            // `push_start..push_end`, so it has room for the element.
+            unsafe {
+                self.block_ptr(self.meta.empty_data_block)
+                    .as_ptr()
+                    .add(len - self.meta.push_start)
+                    .write(element)
+            };
+            self.meta.len += 1;
+            return len;
+        }
+
+        if Self::IS_ZST {
+            // Zero-sized elements aren't stored anywhere.
+            mem::forget(element);
+            self.meta.len += 1;
+            return len;
+        }
+
+        // This is `locate`, without the test logging, which would be far too
+        // noisy when pushing.
+        let (block, slot) = P::locate(len + self.meta.skipped_indices);
+        let block = block - self.meta.skipped_blocks;
+        // Safety: the `SegVec` has room for the element, so its data block
+        // has been allocated, and the element is in bounds of it.
+        unsafe { self.block_ptr(block).as_ptr().add(slot).write(element) };
+
+        self.meta.empty_data_block = block;
+        self.meta.push_start = len - slot;
+        self.meta.push_end = self.meta.push_start + self.block_capacity(block);
+        self.meta.len += 1;
+        len
+    }
+
+    pub fn iter(&self) -> Iter<'_, T, P> {
+        Iter {
+            len: self.len(),
+            blocks: self.index.iter(),
+            next_block: self.meta.skipped_blocks,
+            curr_block: self.zst_block().iter(),
+            _policy: PhantomData,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T, P> {
+        let len = self.len();
+        let curr_block: &mut [T] = if Self::IS_ZST {
+            // Safety: zero-sized elements are all at the same dangling
+            // address, and the `IterMut` borrows the `SegVec` mutably.
+            unsafe { slice::from_raw_parts_mut(NonNull::dangling().as_ptr(), len) }
+        } else {
+            &mut []
+        };
+        IterMut {
+            len,
+            blocks: self.index.iter(),
+            next_block: self.meta.skipped_blocks,
+            curr_block: curr_block.iter_mut(),
+            _policy: PhantomData,
+        }
+    }
+
+    /// Returns a [`SegSlice`] view of the elements in `range`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start of the range is greater than its end, or if the end
+    /// of the range is greater than the length of the `SegVec`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use segvec::SegVec;
+    ///
+    /// let sv: SegVec<i32> = (0..100).collect();
+    /// let slice = sv.slice(10..20);
+    /// assert_eq!(slice.len(), 10);
+    /// assert_eq!(slice[0], 10);
+    /// assert!(slice.iter().copied().eq(10..20));
+    /// ```
+    #[track_caller]
+    pub fn slice(&self, range: impl RangeBounds<usize>) -> SegSlice<'_, T, A, P> {
+        let len = self.len();
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start
+                .checked_add(1)
+                .expect("attempted to slice from after usize::MAX"),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&end) => end
+                .checked_add(1)
+                .expect("attempted to slice up to usize::MAX"),
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => len,
+        };
+        assert!(
+            start <= end,
+            "SegVec slice index starts at {} but ends at {}",
+            start,
+            end
+        );
+        assert!(
+            end <= len,
+            "SegVec range end index {} out of range for SegVec of length {}",
+            end,
+            len
+        );
+
+        SegSlice::new(self, start, end - start)
+    }
+
+    /// Returns an iterator over all contiguous windows of length `size`.
+    ///
+    /// The windows overlap, and each window is a [`SegSlice`]. If a window
+    /// falls entirely within one data block, [`SegSlice::as_slice`] will return
+    /// it as a regular slice. If the `SegVec` is shorter than `size`, the
+    /// iterator returns no values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use segvec::SegVec;
+    ///
+    /// let sv: SegVec<i32> = (0..100).collect();
+    /// let sums = sv.windows(3).map(|w| w.iter().sum::<i32>());
+    /// assert!(sums.eq((0..98).map(|i| i * 3 + 3)));
+    /// ```
+    #[track_caller]
+    pub fn windows(&self, size: usize) -> Windows<'_, T, A, P> {
+        assert_ne!(size, 0, "window size must be non-zero");
+        Windows::new(self, size)
+    }
+
+    /// Returns an iterator over `chunk_size` elements of the `SegVec` at a
+    /// time, starting at the beginning of the `SegVec`.
+    ///
+    /// The chunks are [`SegSlice`]s and do not overlap. If `chunk_size` does
+    /// not divide the length of the `SegVec`, then the last chunk will not
+    /// have length `chunk_size`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chunk_size` is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use segvec::SegVec;
+    ///
+    /// let sv: SegVec<i32> = (0..10).collect();
+    /// let lens: Vec<usize> = sv.chunks(4).map(|chunk| chunk.len()).collect();
+    /// assert_eq!(lens, vec![4, 4, 2]);
+    /// ```
+    #[track_caller]
+    pub fn chunks(&self, chunk_size: usize) -> Chunks<'_, T, A, P> {
+        assert_ne!(chunk_size, 0, "chunk size must be non-zero");
+        Chunks::new(self, chunk_size)
+    }
+
+    /// Returns an iterator over the elements in `start..end`.
+    fn range_iter(&self, start: usize, end: usize) -> Iter<'_, T, P> {
+        debug_assert!(start <= end);
+        debug_assert!(end <= self.len());
+        if start == end {
+            return Iter {
+                len: 0,
+                blocks: [].iter(),
+                next_block: self.meta.skipped_blocks,
+                curr_block: [].iter(),
+                _policy: PhantomData,
+            };
+        }
+
+        let (block, idx) = self.locate(start);
+        let curr_block = &self.block_slice(block)[idx..];
+        Iter {
+            len: end - start,
+            blocks: self.index.get(block + 1..).unwrap_or_default().iter(),
+            next_block: self.meta.skipped_blocks + block + 1,
+            curr_block: curr_block.iter(),
+            _policy: PhantomData,
+        }
+    }
+
+    /// Returns the elements in `start..end` as a slice, if they are all in the
+    /// same data block.
+    fn range_as_slice(&self, start: usize, end: usize) -> Option<&[T]> {
+        debug_assert!(start <= end);
+        debug_assert!(end <= self.len());
+        if start == end {
+            return Some(&[]);
+        }
+
+        let (first_block, first) = self.locate(start);
+        let (last_block, last) = self.locate(end - 1);
+        if first_block != last_block {
+            return None;
+        }
+
+        Some(&self.block_slice(first_block)[first..=last])
+    }
+
+    /// Swaps two elements in the `SegVec`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `a` or `b` are out of bounds.
+    #[track_caller]
+    fn swap(&mut self, a: usize, b: usize) {
+        let len = self.len();
+        assert!(
+            a < len && b < len,
+            "SegVec index out of bounds: the len is {} but the indices are {} and {}",
+            len,
+            a,
+            b,
+        );
+
+        // Safety: both elements are in bounds, and `ptr::swap` allows them to
+        // be the same element.
+        unsafe { ptr::swap(self.elem_ptr(a), self.elem_ptr(b)) };
+    }
+
+    /// Reverses the order of elements in the `SegVec`, in place.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use segvec::SegVec;
+    ///
+    /// let mut sv: SegVec<i32> = (0..10).collect();
+    /// sv.reverse();
+    /// assert!(sv.iter().copied().eq((0..10).rev()));
+    /// ```
+    pub fn reverse(&mut self) {
+        self.reverse_range(0, self.len());
+    }
+
+    /// Rotates the `SegVec` in-place such that the first `mid` elements move
+    /// to the end, while the last `self.len() - mid` elements move to the
+    /// front.
+    ///
+    /// After calling `rotate_left`, the element previously at index `mid` will
+    /// become the first element in the `SegVec`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `mid` is greater than the length of the `SegVec`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use segvec::SegVec;
+    ///
+    /// let mut sv: SegVec<char> = "abcdef".chars().collect();
+    /// sv.rotate_left(2);
+    /// assert!(sv.iter().copied().eq("cdefab".chars()));
+    /// ```
+    #[track_caller]
+    pub fn rotate_left(&mut self, mid: usize) {
+        let len = self.len();
+        assert!(
+            mid <= len,
+            "rotate_left mid {} greater than length of SegVec {}",
+            mid,
+            len
+        );
+
+        // Rotating is the same as reversing both halves, and then reversing
+        // the whole thing.
+        self.reverse_range(0, mid);
+        self.reverse_range(mid, len);
+        self.reverse_range(0, len);
+    }
+
+    /// Rotates the `SegVec` in-place such that the first `self.len() - k`
+    /// elements move to the end, while the last `k` elements move to the
+    /// front.
+    ///
+    /// After calling `rotate_right`, the element previously at index
+    /// `self.len() - k` will become the first element in the `SegVec`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is greater than the length of the `SegVec`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use segvec::SegVec;
+    ///
+    /// let mut sv: SegVec<char> = "abcdef".chars().collect();
+    /// sv.rotate_right(2);
+    /// assert!(sv.iter().copied().eq("efabcd".chars()));
+    /// ```
+    #[track_caller]
+    pub fn rotate_right(&mut self, k: usize) {
+        let len = self.len();
+        assert!(
+            k <= len,
+            "rotate_right k {} greater than length of SegVec {}",
+            k,
+            len
+        );
+        self.rotate_left(len - k);
+    }
+
+    /// Fills the `SegVec` with elements by cloning `value`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use segvec::SegVec;
+    ///
+    /// let mut sv: SegVec<i32> = (0..10).collect();
+    /// sv.fill(1);
+    /// assert!(sv.iter().all(|&x| x == 1));
+    /// ```
+    pub fn fill(&mut self, value: T)
+    where
+        T: Clone,
+    {
+        for block in 0..self.non_empty_blocks() {
+            self.block_slice_mut(block).fill(value.clone());
+        }
+    }
+
+    /// Fills the `SegVec` with elements returned by calling a closure
+    /// repeatedly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use segvec::SegVec;
+    ///
+    /// let mut sv: SegVec<i32> = (0..10).collect();
+    /// let mut n = 0;
+    /// sv.fill_with(|| { n += 2; n });
+    /// assert!(sv.iter().copied().eq((1..=10).map(|i| i * 2)));
+    /// ```
+    pub fn fill_with<F>(&mut self, mut f: F)
+    where
+        F: FnMut() -> T,
+    {
+        for block in 0..self.non_empty_blocks() {
+            self.block_slice_mut(block).fill_with(&mut f);
+        }
+    }
+
+    /// Reverses the elements in `start..end`, in place.
+    fn reverse_range(&mut self, start: usize, end: usize) {
+        debug_assert!(start <= end);
+        debug_assert!(end <= self.len());
+        if end - start < 2 {
+            return;
+        }
 
-    pub fn with_capacity(capacity: usize) -> Self {
-        let mut this = Self::new();
-        this.reserve(capacity);
-        this
+        // Walk one location forwards from the start of the range, and another
+        // backwards from the end, swapping the elements at each pair of
+        // locations until they meet in the middle.
+        let (mut front_block, mut front) = self.locate(start);
+        let (mut back_block, mut back) = self.locate(end - 1);
+        let mut front_capacity = self.block_capacity(front_block);
+        for _ in 0..(end - start) / 2 {
+            // Safety: both locations are within the range, which is in bounds.
+            unsafe {
+                ptr::swap(
+                    self.block_ptr(front_block).as_ptr().add(front),
+                    self.block_ptr(back_block).as_ptr().add(back),
+                )
+            };
+
+            front += 1;
+            if front == front_capacity {
+                front_block += 1;
+                front = 0;
+                front_capacity = self.block_capacity(front_block);
+            }
+
+            if back == 0 {
+                back_block -= 1;
+                back = self.block_capacity(back_block);
+            }
+            back -= 1;
+        }
     }
 
-    /// Returns the number of elements the `SegVec` can hold without
-    /// reallocating.
+    /// Binary searches this `SegVec` for a given element.
+    ///
+    /// This behaves like [`slice::binary_search`]: the `SegVec` should be
+    /// sorted, and if it is not, the returned result is unspecified and
+    /// meaningless.
+    ///
+    /// If the value is found, then [`Ok`] is returned, containing the index of
+    /// the matching element. If there are multiple matches, then any one of the
+    /// matches could be returned. If the value is not found, then [`Err`] is
+    /// returned, containing the index where a matching element could be
+    /// inserted while maintaining sorted order.
     ///
     /// # Examples
     ///
     /// ```
     /// use segvec::SegVec;
     ///
-    /// let sv: SegVec<i32> = SegVec::with_capacity(10);
-    /// assert!(sv.capacity() >= 10);
+    /// let sv: SegVec<i32> = (0..100).map(|i| i * 2).collect();
+    /// assert_eq!(sv.binary_search(&42), Ok(21));
+    /// assert_eq!(sv.binary_search(&43), Err(22));
+    /// assert_eq!(sv.binary_search(&1000), Err(100));
     /// ```
-    #[inline]
-    pub fn capacity(&self) -> usize {
-        self.capacity
+    pub fn binary_search(&self, x: &T) -> Result<usize, usize>
+    where
+        T: Ord,
+    {
+        self.binary_search_by(|elem| elem.cmp(x))
     }
 
-    /// Returns the number of elements in the `SegVec`, also referred to
-    /// as its 'length'.
+    /// Binary searches this `SegVec` with a comparator function.
+    ///
+    /// The comparator function should return an order code that indicates
+    /// whether its argument is `Less`, `Equal` or `Greater` than the desired
+    /// target. See [`slice::binary_search_by`] for details.
+    ///
+    /// Rather than binary searching by indexing into the `SegVec` (which would
+    /// locate the data block for every probe), this first binary searches the
+    /// data blocks by their last element, and then binary searches the
+    /// elements of a single data block.
+    pub fn binary_search_by<'segvec, F>(&'segvec self, mut f: F) -> Result<usize, usize>
+    where
+        F: FnMut(&'segvec T) -> Ordering,
+    {
+        let blocks = self.non_empty_blocks();
+
+        // 1. Find the first data block whose last element is not `Less` than
+        //    the target. If the target is in the `SegVec` at all, it's in this
+        //    block.
+        let (mut lo, mut hi) = (0, blocks);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            let last = self
+                .block_slice(mid)
+                .last()
+                .expect("non-empty blocks must have a last element");
+            if f(last) == Ordering::Less {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+
+        // Every element is less than the target.
+        if lo == blocks {
+            return Err(self.len());
+        }
+
+        // 2. Search within that data block.
+        let start = self.block_start(lo);
+        let block: &'segvec [T] = self.block_slice(lo);
+        block
+            .binary_search_by(f)
+            .map(|i| start + i)
+            .map_err(|i| start + i)
+    }
+
+    /// Binary searches this `SegVec` with a key extraction function.
+    ///
+    /// See [`slice::binary_search_by_key`] for details.
+    pub fn binary_search_by_key<'segvec, B, F>(
+        &'segvec self,
+        b: &B,
+        mut f: F,
+    ) -> Result<usize, usize>
+    where
+        F: FnMut(&'segvec T) -> B,
+        B: Ord,
+    {
+        self.binary_search_by(|elem| f(elem).cmp(b))
+    }
+
+    /// Returns the index of the partition point according to the given
+    /// predicate (the index of the first element of the second partition).
+    ///
+    /// The `SegVec` is assumed to be partitioned according to the given
+    /// predicate. See [`slice::partition_point`] for details.
     ///
     /// # Examples
     ///
-    // TODO(eliza): fix this example.
-    /// ```ignore
-    /// use segvec::segvec;
+    /// ```
+    /// use segvec::SegVec;
     ///
-    /// let sv = segvec![1, 2, 3];
-    /// assert_eq!(a.len(), 3);
+    /// let sv: SegVec<i32> = (0..100).collect();
+    /// assert_eq!(sv.partition_point(|&x| x < 42), 42);
     /// ```
-    #[doc(alias = "length")]
-    #[inline]
-    pub fn len(&self) -> usize {
-        self.meta.len
+    pub fn partition_point<F>(&self, mut pred: F) -> usize
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.binary_search_by(|elem| {
+            if pred(elem) {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            }
+        })
+        .unwrap_or_else(|i| i)
     }
 
-    /// Reserves capacity for at least `additional` more elements to be inserted
-    /// in the given `SegVec<T>`. The collection may reserve more space to avoid
-    /// frequent reallocations. After calling `reserve`, capacity will be
-    /// greater than or equal to `self.len() + additional`. Does nothing if
-    /// capacity is already sufficient.
+    /// Reorders the `SegVec` such that the element at `index` is at its final
+    /// sorted position, and returns a mutable reference to it.
+    ///
+    /// After reordering, every element before `index` is less than or equal
+    /// to the element at `index`, and every element after it is greater than
+    /// or equal to it. See [`slice::select_nth_unstable`] for details.
+    ///
+    /// This is an introselect: a quickselect which falls back to
+    /// median-of-medians pivots if it makes too little progress, so it runs in
+    /// _O(n)_ time in the worst case.
     ///
     /// # Panics
     ///
-    /// Panics if the new capacity exceeds `isize::MAX` bytes.
+    /// Panics if `index >= len()`.
     ///
     /// # Examples
     ///
     /// ```
     /// use segvec::SegVec;
     ///
-    /// let mut sv: SegVec<i32> = SegVec::with_capacity(1);
-    /// sv.reserve(10);
-    /// assert!(sv.capacity() >= 11);
+    /// let mut sv: SegVec<i32> = vec![-5, 4, 1, -3, 2].into_iter().collect();
+    /// assert_eq!(*sv.select_nth_unstable(2), 1);
     /// ```
-    pub fn reserve(&mut self, mut additional: usize) {
-        assert!(additional < isize::MAX as usize);
-
-        if additional == 0 {
-            return;
-        }
+    #[track_caller]
+    pub fn select_nth_unstable(&mut self, index: usize) -> &mut T
+    where
+        T: Ord,
+    {
+        self.select_nth_unstable_by(index, T::cmp)
+    }
 
-        if self.capacity == 0 {
-            // If the requested capacity is not a power of two, round up to the next
-            // power of two.
-            if test_dbg!(!additional.is_power_of_two()) {
-                additional = test_dbg!(additional.next_power_of_two());
-            };
+    /// Reorders the `SegVec` with a comparator function such that the element
+    /// at `index` is at its final sorted position.
+    ///
+    /// See [`SegVec::select_nth_unstable`] for details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= len()`.
+    #[track_caller]
+    pub fn select_nth_unstable_by<F>(&mut self, index: usize, mut compare: F) -> &mut T
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        let len = self.len();
+        assert!(
+            index < len,
+            "partition_at_index index {} greater than length of SegVec {}",
+            index,
+            len
+        );
 
-            // If the capacity is less than the reasonable minimum capacity for the
-            // size of elements in the `SegVec`, use that capacity instead.
-            test_dbg!(let additional = cmp::max(additional, Self::MIN_NON_ZERO_CAP););
-            self.initialize(additional);
-            return;
-        }
+        self.select_in(0, len - 1, index, &mut compare);
+        &mut self[index]
+    }
 
-        #[cfg(debug_assertions)]
-        debug_assert!(self.is_initialized);
+    /// Reorders the `SegVec` with a key extraction function such that the
+    /// element at `index` is at its final sorted position.
+    ///
+    /// See [`SegVec::select_nth_unstable`] for details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= len()`.
+    #[track_caller]
+    pub fn select_nth_unstable_by_key<K, F>(&mut self, index: usize, mut f: F) -> &mut T
+    where
+        F: FnMut(&T) -> K,
+        K: Ord,
+    {
+        self.select_nth_unstable_by(index, |a, b| f(a).cmp(&f(b)))
+    }
 
-        let _ = test_dbg!((self.capacity(), self.len(), additional));
+    /// Reorders the elements in `lo..=hi` such that the element at `index` is
+    /// at its final sorted position within that range.
+    fn select_in<F>(&mut self, mut lo: usize, mut hi: usize, index: usize, compare: &mut F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        // Median-of-three pivots are fast, but an adversarial input can make
+        // every partition peel off only a few elements. After `2 * log2(n)`
+        // partitions, switch to median-of-medians pivots, which always
+        // discard a constant fraction of the range.
+        let mut limit = 2 * (usize::BITS - (hi - lo + 1).leading_zeros());
+        while lo < hi {
+            let pivot = if limit > 0 {
+                limit -= 1;
+                self.median_of_three(lo, hi, compare)
+            } else {
+                self.median_of_medians(lo, hi, compare)
+            };
 
-        if test_dbg!(self.capacity() - self.len() >= additional) {
-            return;
+            let (lt, gt) = self.partition3(lo, hi, pivot, compare);
+            if index < lt {
+                hi = lt - 1;
+            } else if index > gt {
+                lo = gt + 1;
+            } else {
+                break;
+            }
         }
+    }
 
-        while test_dbg!(self.capacity() - self.len() < additional) {
-            self.grow();
-            let _ = test_dbg!(&self.meta);
+    /// Sorts the first, middle, and last elements in `lo..=hi`, and returns
+    /// the index of their median.
+    fn median_of_three<F>(&mut self, lo: usize, hi: usize, compare: &mut F) -> usize
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        let mid = lo + (hi - lo) / 2;
+        if compare(&self[mid], &self[lo]) == Ordering::Less {
+            self.swap(mid, lo);
         }
+        if compare(&self[hi], &self[lo]) == Ordering::Less {
+            self.swap(hi, lo);
+        }
+        if compare(&self[hi], &self[mid]) == Ordering::Less {
+            self.swap(hi, mid);
+        }
+        mid
     }
 
-    // this code was implemented from a computer science paper lol
-    #[allow(clippy::many_single_char_names)]
-    fn locate(&self, i: usize) -> (usize, usize) {
-        const BITS2: usize = (usize::BITS - 1) as usize;
-        // TODO(eliza): it is almost certainly possible to optimize this using
-        // the `log2` of the current block size...
-
-        // 1. Let `r` denote the binary representation of `i + 1`, with all
-        //    leading zeroes removed.
-        test_dbg!(let r = i + 1 + self.meta.skipped_indices;);
-        // 2. Note that the desired element `i` is element `e` of data block `b`
-        //    of superblock `k`, where:
-        //  (a). `k = |r| - 1`
-        test_dbg!(let k = BITS2.saturating_sub(r.leading_zeros() as usize););
-        //   (c). `e` is the last `ceil(k/2)` bits of `r`.
-        test_dbg!(let e_bits = (k + 1) >> 1;);
-        test_dbg!(let e = r & !(usize::MAX << e_bits););
-        test_dbg!(let r = r >> e_bits;);
-        //  (b). `b` is the last `floor(k/2)` bits of `r` immediately after the
-        //       leading 1-bit
-        test_dbg!(let b_bits = k >> 1;);
-        test_dbg!(let b = r & !(usize::MAX << b_bits););
-        // 3. let `p = 2^k - 1` be the number of datablocks in superblocks prior to
-        //   `SB[k]`.
-        test_dbg!(let p = (1 << e_bits) + (1 << b_bits) - 2;);
-
-        // 4. Return the location of element `e` in data block `DB[p + b]`.
-        // NOTE: also compensate for skipped low-size blocks.
-        test_dbg!(let data_block = p + b - self.meta.skipped_blocks;);
+    /// Returns the index of an element in `lo..=hi` which is greater than at
+    /// least 30% of the elements in the range, and less than at least 30% of
+    /// them.
+    ///
+    /// This sorts each group of five elements, moves the groups' medians to
+    /// the front of the range, and selects the median of those.
+    fn median_of_medians<F>(&mut self, lo: usize, hi: usize, compare: &mut F) -> usize
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        let mut groups = 0;
+        let mut start = lo;
+        loop {
+            let end = start + cmp::min(4, hi - start);
+            for i in start + 1..=end {
+                let mut j = i;
+                while j > start && compare(&self[j], &self[j - 1]) == Ordering::Less {
+                    self.swap(j, j - 1);
+                    j -= 1;
+                }
+            }
+            // Every group before this one has already been visited, so this
+            // only overwrites elements we're done with.
+            self.swap(lo + groups, start + (end - start) / 2);
+            groups += 1;
 
-        // If the data block index is out of bounds, panic with a nicer
-        // assertion with more debugging information.
-        debug_assert!(
-            data_block < self.index.len(),
-            "assertion failed: data_block < self.index.len(); \
-            data_block={}; self.index.len()={}; p={}; b={}; \
-            metadata={:#?}",
-            data_block,
-            self.index.len(),
-            p,
-            b,
-            self.meta,
-        );
+            if end == hi {
+                break;
+            }
+            start = end + 1;
+        }
 
-        (data_block, test_dbg!(e))
+        let mid = lo + (groups - 1) / 2;
+        self.select_in(lo, lo + groups - 1, mid, compare);
+        mid
     }
 
-    pub fn is_empty(&self) -> bool {
-        self.meta.len == 0
+    /// Three-way partitions the elements in `lo..=hi` around the element at
+    /// `pivot`, returning the range of indices holding elements equal to the
+    /// pivot.
+    fn partition3<F>(
+        &mut self,
+        lo: usize,
+        hi: usize,
+        pivot: usize,
+        compare: &mut F,
+    ) -> (usize, usize)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        self.swap(lo, pivot);
+
+        // Dijkstra's "Dutch national flag" partition. Everything in `lt..i`
+        // is equal to the pivot, so the pivot is always at `lt`.
+        let (mut lt, mut i, mut gt) = (lo, lo + 1, hi);
+        while i <= gt {
+            match compare(&self[i], &self[lt]) {
+                Ordering::Less => {
+                    self.swap(lt, i);
+                    lt += 1;
+                    i += 1;
+                }
+                Ordering::Greater => {
+                    self.swap(i, gt);
+                    gt -= 1;
+                }
+                Ordering::Equal => i += 1,
+            }
+        }
+
+        (lt, gt)
     }
 
-    pub fn get(&self, idx: usize) -> Option<&T> {
-        if idx > self.meta.len {
-            return None;
+    /// Returns the number of data blocks which contain at least one element.
+    fn non_empty_blocks(&self) -> usize {
+        if self.is_empty() {
+            return 0;
         }
 
-        let (block, idx) = self.locate(idx);
-        self.index.get(block)?.elements.get(idx)
+        self.meta.empty_data_block + 1
     }
 
-    pub fn get_mut(&mut self, idx: usize) -> Option<&mut T> {
-        if idx > self.meta.len {
-            return None;
+    /// Returns the index of the first element in the data block at position
+    /// `block` in the index.
+    ///
+    /// This is the inverse of `SegVec::locate`.
+    fn block_start(&self, block: usize) -> usize {
+        P::block_start(block + self.meta.skipped_blocks) - self.meta.skipped_indices
+    }
+
+    /// Returns the capacity of the data block at position `block` in the
+    /// index.
+    fn block_capacity(&self, block: usize) -> usize {
+        if Self::IS_ZST {
+            return usize::MAX;
         }
 
-        let (block, idx) = self.locate(idx);
-        self.index.get_mut(block)?.elements.get_mut(idx)
+        P::block_capacity(block + self.meta.skipped_blocks)
     }
 
-    pub fn push(&mut self, element: T) -> usize {
-        if self.capacity == 0 {
-            self.initialize(Self::MIN_NON_ZERO_CAP);
-        } else {
-            #[cfg(debug_assertions)]
-            debug_assert!(self.is_initialized);
-        };
+    /// Returns the number of elements in the data block at position `block`
+    /// in the index.
+    ///
+    /// Every data block before the one containing the last element is full,
+    /// and every data block after it is empty.
+    fn block_len(&self, block: usize) -> usize {
+        cmp::min(
+            self.meta.len.saturating_sub(self.block_start(block)),
+            self.block_capacity(block),
+        )
+    }
 
-        let mut curr_block = &mut self.index[self.meta.empty_data_block];
-
-        // Is the current block to push in full?
-        if curr_block.is_full() {
-            // Is the current block the last one (e.g. are we out of blocks)?
-            // If so, allocate a new block. Otherwise, we have additional free
-            // blocks to fill (due to `reserve`/`with_capacity`) calls.
-            // NOTE: the Brodnik et al paper doesn't consider that you might
-            //       want to reserve capacity, so this is one of our deviations
-            //       from their algorithm.
-            if self.meta.empty_data_block == self.index.len() - 1 {
-                self.grow();
-            }
+    /// Returns the elements in the data block at position `block` in the
+    /// index.
+    fn block_slice(&self, block: usize) -> &[T] {
+        let len = self.block_len(block);
+        // Safety: the first `len` elements in the block are initialized.
+        unsafe { slice::from_raw_parts(self.block_ptr(block).as_ptr(), len) }
+    }
 
-            self.meta.empty_data_block += 1;
-            curr_block = &mut self.index[self.meta.empty_data_block];
-        }
+    /// Returns the elements in the data block at position `block` in the
+    /// index, mutably.
+    fn block_slice_mut(&mut self, block: usize) -> &mut [T] {
+        let len = self.block_len(block);
+        // Safety: the first `len` elements in the block are initialized, and
+        // we have exclusive access to them.
+        unsafe { slice::from_raw_parts_mut(self.block_ptr(block).as_ptr(), len) }
+    }
 
-        curr_block.push(element);
+    /// Returns a pointer to the data block at position `block` in the index.
+    ///
+    /// The data block of a `SegVec` of zero-sized elements is dangling.
+    #[inline]
+    fn block_ptr(&self, block: usize) -> NonNull<T> {
+        if Self::IS_ZST {
+            return NonNull::dangling();
+        }
 
-        let len = self.meta.len;
-        self.meta.len += 1;
-        len
+        self.index[block]
     }
 
-    pub fn iter(&self) -> Iter<'_, T> {
-        let mut blocks = self.index.iter();
-        let curr_block = blocks
-            .next()
-            .map(|block| block.elements.iter())
-            .unwrap_or_else(|| [].iter());
-        Iter {
-            len: self.len(),
-            blocks,
-            curr_block,
+    /// Returns every element of a `SegVec` of zero-sized elements as a single
+    /// slice. Otherwise, returns an empty slice.
+    ///
+    /// The data block of a `SegVec` of zero-sized elements isn't stored in the
+    /// index, so its iterators start out with it as their current data block.
+    fn zst_block(&self) -> &[T] {
+        if Self::IS_ZST {
+            return self.block_slice(0);
         }
+
+        &[]
     }
 
-    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
-        let len = self.len();
-        let mut blocks = self.index.iter_mut();
-        let curr_block = blocks
-            .next()
-            .map(|block| block.elements.iter_mut())
-            .unwrap_or_else(|| [].iter_mut());
-        IterMut {
-            len,
-            blocks,
-            curr_block,
-        }
+    /// Returns a pointer to the element at `idx`.
+    ///
+    /// # Safety
+    ///
+    /// `idx` must be less than the `SegVec`'s capacity.
+    unsafe fn elem_ptr(&self, idx: usize) -> *mut T {
+        let (block, idx) = self.locate(idx);
+        // Safety: guaranteed by the caller.
+        unsafe { self.block_ptr(block).as_ptr().add(idx) }
     }
 
-    fn grow(&mut self) {
-        if self.capacity == 0 {
-            self.initialize(Self::MIN_NON_ZERO_CAP);
-        } else {
-            #[cfg(debug_assertions)]
-            debug_assert!(self.is_initialized);
+    /// Sets the length of the `SegVec`, without dropping or initializing any
+    /// elements.
+    ///
+    /// # Safety
+    ///
+    /// `len` must be at most the `SegVec`'s capacity, and the first `len`
+    /// elements must be initialized. Any elements after the first `len` are
+    /// no longer owned by the `SegVec`.
+    #[cfg(any(target_has_atomic = "ptr", feature = "rayon"))]
+    unsafe fn set_len(&mut self, len: usize) {
+        debug_assert!(len <= self.capacity());
+        self.meta.len = len;
+        self.meta.empty_data_block = match len.checked_sub(1) {
+            Some(last) => self.locate(last).0,
+            None => 0,
         };
+        self.meta.push_start = 0;
+        self.meta.push_end = 0;
+    }
+
+    /// Allocates new data blocks until there is room for at least
+    /// `additional` more elements.
+    ///
+    /// If an allocation fails, the `SegVec` is left unchanged.
+    fn try_grow(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let _ = test_dbg!((self.capacity(), self.len(), additional));
+
+        if test_dbg!(self.capacity() - self.len() >= additional) {
+            return Ok(());
+        }
+
+        // First, figure out how many blocks we need, so that we can make room
+        // for all of them in the index up front.
+        let index_len = self.index.len();
+        let mut capacity = self.capacity;
+        let mut new_blocks = 0;
+        while test_dbg!(capacity - self.len() < additional) {
+            capacity += self.block_capacity(index_len + new_blocks);
+            new_blocks += 1;
+        }
+        self.index.try_reserve(new_blocks)?;
+
+        // Now, actually allocate the new blocks. Don't update the capacity
+        // until all the blocks have been allocated, so that the `SegVec` is
+        // unchanged if an allocation fails.
+        for block in index_len..index_len + new_blocks {
+            match allocator::allocate(self.index.allocator(), self.block_capacity(block)) {
+                Ok(block) => self.index.push(block),
+                Err(error) => {
+                    self.dealloc_blocks(index_len);
+                    return Err(error);
+                }
+            }
+        }
 
-        self.meta.grow();
-        self.index.push(Block::new(self.meta.block_cap));
-        self.capacity += self.meta.block_cap;
+        self.capacity = test_dbg!(capacity);
+        Ok(())
     }
 
-    fn initialize(&mut self, capacity: usize) {
+    /// Allocates the first data block, skipping every data block in the
+    /// growth policy's layout before the first one with `capacity` elements.
+    fn try_initialize(&mut self, capacity: usize) -> Result<(), TryReserveError> {
         #[cfg(debug_assertions)]
         debug_assert!(!self.is_initialized);
         debug_assert!(capacity.is_power_of_two());
-        debug_assert!(capacity >= Self::MIN_NON_ZERO_CAP);
-
-        // Grow the metadata up to the requested capacity.
-        while test_dbg!(self.meta.block_cap) < capacity {
-            self.meta.grow();
-            self.meta.skipped_blocks += 1;
-            self.meta.skipped_indices += self.meta.block_cap;
-            let _ = test_dbg!(&self.meta);
+        debug_assert!(capacity <= Self::MAX_FIRST_BLOCK_CAP);
+
+        // Allocate the first actual data block, and make room for it in the
+        // index. The skipped low-size blocks aren't stored in the index. If
+        // the growth policy has no data block of the requested capacity, the
+        // first data block may be a different size.
+        let meta = Meta::skip_to::<P>(capacity);
+        let capacity = P::block_capacity(meta.skipped_blocks);
+        let block = allocator::allocate(self.index.allocator(), capacity)?;
+        if let Err(error) = self.index.try_reserve(1) {
+            // Safety: the block was just allocated by this allocator, with
+            // this capacity.
+            unsafe { allocator::deallocate(self.index.allocator(), block, capacity) };
+            return Err(error);
         }
 
-        // Build the index, in a vector with enough room for at least the number
-        // of skipped data blocks plus the first actual data block.
-        self.index.reserve(self.meta.skipped_blocks);
-
-        // Grow the metadata again and push the first actual data block.
-        self.meta.grow();
-        self.index.push(Block::new(capacity));
-        debug_assert_eq!(self.meta.block_cap, capacity);
+        // Skip the metadata ahead to the first data block.
+        self.meta = meta;
+        self.index.push(block);
 
         let _ = test_dbg!(&self.meta);
 
@@ -433,16 +1714,39 @@ impl<T> SegVec<T> {
             self.is_initialized = true;
         }
         self.capacity = capacity;
+        Ok(())
     }
 
-    // TODO(eliza): consider making this an API?
-    #[cfg(test)]
-    fn debug_details(&self) -> DebugDetails<'_, T> {
-        DebugDetails(self)
+    /// Drops the elements in, and deallocates, every data block after the
+    /// first `len` blocks in the index.
+    fn dealloc_blocks(&mut self, len: usize) {
+        if Self::IS_ZST {
+            if len == 0 {
+                let elements = self.block_slice_mut(0) as *mut [T];
+                // Safety: the elements are initialized, and the `SegVec`'s
+                // only data block is being removed.
+                unsafe { ptr::drop_in_place(elements) };
+            }
+            return;
+        }
+
+        let alloc = self.index.allocator();
+        for (block, &ptr) in self.index.iter().enumerate().skip(len) {
+            let block_len = self.block_len(block);
+            // Safety: every block in the index was allocated by the index's
+            // allocator, with the capacity given by its position, and is
+            // removed from the index below. The first `block_len` elements
+            // in the block are initialized.
+            unsafe {
+                ptr::drop_in_place(ptr::slice_from_raw_parts_mut(ptr.as_ptr(), block_len));
+                allocator::deallocate(alloc, ptr, self.block_capacity(block));
+            }
+        }
+        self.index.truncate(len);
     }
 }
 
-impl<T> Index<usize> for SegVec<T> {
+impl<T, A: Allocator, P: GrowthPolicy> Index<usize> for SegVec<T, A, P> {
     type Output = T;
 
     #[track_caller]
@@ -458,7 +1762,7 @@ impl<T> Index<usize> for SegVec<T> {
     }
 }
 
-impl<T> IndexMut<usize> for SegVec<T> {
+impl<T, A: Allocator, P: GrowthPolicy> IndexMut<usize> for SegVec<T, A, P> {
     #[track_caller]
     fn index_mut(&mut self, idx: usize) -> &mut Self::Output {
         let len = self.meta.len;
@@ -472,7 +1776,7 @@ impl<T> IndexMut<usize> for SegVec<T> {
     }
 }
 
-impl<T> Extend<T> for SegVec<T> {
+impl<T, A: Allocator, P: GrowthPolicy> Extend<T> for SegVec<T, A, P> {
     fn extend<I>(&mut self, iter: I)
     where
         I: IntoIterator<Item = T>,
@@ -513,8 +1817,8 @@ impl<T> FromIterator<T> for SegVec<T> {
     }
 }
 
-impl<'segvec, T> IntoIterator for &'segvec SegVec<T> {
-    type IntoIter = Iter<'segvec, T>;
+impl<'segvec, T, A: Allocator, P: GrowthPolicy> IntoIterator for &'segvec SegVec<T, A, P> {
+    type IntoIter = Iter<'segvec, T, P>;
     type Item = &'segvec T;
 
     fn into_iter(self) -> Self::IntoIter {
@@ -522,8 +1826,8 @@ impl<'segvec, T> IntoIterator for &'segvec SegVec<T> {
     }
 }
 
-impl<'segvec, T> IntoIterator for &'segvec mut SegVec<T> {
-    type IntoIter = IterMut<'segvec, T>;
+impl<'segvec, T, A: Allocator, P: GrowthPolicy> IntoIterator for &'segvec mut SegVec<T, A, P> {
+    type IntoIter = IterMut<'segvec, T, P>;
     type Item = &'segvec mut T;
 
     fn into_iter(self) -> Self::IntoIter {
@@ -531,7 +1835,7 @@ impl<'segvec, T> IntoIterator for &'segvec mut SegVec<T> {
     }
 }
 
-impl<T: fmt::Debug> fmt::Debug for SegVec<T> {
+impl<T: fmt::Debug, A: Allocator, P: GrowthPolicy> fmt::Debug for SegVec<T, A, P> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_list().entries(self.iter()).finish()
     }
@@ -543,16 +1847,41 @@ impl<T> Default for SegVec<T> {
     }
 }
 
+impl<T, A: Allocator, P: GrowthPolicy> Drop for SegVec<T, A, P> {
+    fn drop(&mut self) {
+        self.dealloc_blocks(0);
+    }
+}
+
+// Safety: a `SegVec` owns its elements and its allocator, and only provides
+// access to its elements through references to the `SegVec`, just like `Vec`.
+unsafe impl<T: Send, A: Allocator + Send, P: GrowthPolicy> Send for SegVec<T, A, P> {}
+unsafe impl<T: Sync, A: Allocator + Sync, P: GrowthPolicy> Sync for SegVec<T, A, P> {}
+
 // === impl Iter ===
 
-impl<'segvec, T> Iterator for Iter<'segvec, T> {
+// Safety: an `Iter` only hands out shared references to the elements, like
+// `slice::Iter`. The pointers in the index are only read.
+unsafe impl<T: Sync, P: GrowthPolicy> Send for Iter<'_, T, P> {}
+unsafe impl<T: Sync, P: GrowthPolicy> Sync for Iter<'_, T, P> {}
+
+impl<'segvec, T, P: GrowthPolicy> Iterator for Iter<'segvec, T, P> {
     type Item = &'segvec T;
     fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+
         loop {
             if let Some(elem) = self.curr_block.next() {
+                self.len -= 1;
                 return Some(elem);
             }
-            self.curr_block = self.blocks.next()?.elements.iter();
+            let block = self.blocks.next()?;
+            let len = cmp::min(self.len, P::block_capacity(self.next_block));
+            self.next_block += 1;
+            // Safety: the first `len` elements of the block are initialized.
+            self.curr_block = unsafe { slice::from_raw_parts(block.as_ptr(), len) }.iter();
         }
     }
 
@@ -561,7 +1890,7 @@ impl<'segvec, T> Iterator for Iter<'segvec, T> {
     }
 }
 
-impl<T> ExactSizeIterator for Iter<'_, T> {
+impl<T, P: GrowthPolicy> ExactSizeIterator for Iter<'_, T, P> {
     fn len(&self) -> usize {
         self.len
     }
@@ -569,14 +1898,29 @@ impl<T> ExactSizeIterator for Iter<'_, T> {
 
 // === impl IterMut ===
 
-impl<'segvec, T> Iterator for IterMut<'segvec, T> {
+// Safety: an `IterMut` hands out mutable references to the elements, like
+// `slice::IterMut`. The pointers in the index are only read.
+unsafe impl<T: Send, P: GrowthPolicy> Send for IterMut<'_, T, P> {}
+unsafe impl<T: Sync, P: GrowthPolicy> Sync for IterMut<'_, T, P> {}
+
+impl<'segvec, T, P: GrowthPolicy> Iterator for IterMut<'segvec, T, P> {
     type Item = &'segvec mut T;
     fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+
         loop {
             if let Some(elem) = self.curr_block.next() {
+                self.len -= 1;
                 return Some(elem);
             }
-            self.curr_block = self.blocks.next()?.elements.iter_mut();
+            let block = self.blocks.next()?;
+            let len = cmp::min(self.len, P::block_capacity(self.next_block));
+            self.next_block += 1;
+            // Safety: the first `len` elements of the block are initialized,
+            // and the iterator borrows the `SegVec` mutably.
+            self.curr_block = unsafe { slice::from_raw_parts_mut(block.as_ptr(), len) }.iter_mut();
         }
     }
 
@@ -585,7 +1929,7 @@ impl<'segvec, T> Iterator for IterMut<'segvec, T> {
     }
 }
 
-impl<T> ExactSizeIterator for IterMut<'_, T> {
+impl<T, P: GrowthPolicy> ExactSizeIterator for IterMut<'_, T, P> {
     fn len(&self) -> usize {
         self.len
     }
@@ -598,87 +1942,26 @@ impl Meta {
     const fn empty() -> Self {
         Self {
             len: 0,
-            superblock: 0,
-            sb_cap: 1,
-            sb_len: 0,
-            block_cap: 1,
             skipped_blocks: 0,
             skipped_indices: 0,
             empty_data_block: 0,
+            push_start: 0,
+            push_end: 0,
         }
     }
 
-    /// Grow the `SegVec` described by this `Meta`.
-    ///
-    /// This does *not* allocate a new data block. Instead, it increments the
-    /// variables tracking the numbers of blocks and superblocks, and increments
-    /// the block size or superblock size, as needed.
+    /// Returns metadata describing an empty `SegVec` whose first data block
+    /// is the first data block in `P`'s layout with `capacity` elements.
     ///
-    /// This should be called prior to allocating a new data block.
-    fn grow(&mut self) {
-        // 1. If the last non-empty data block `DB[d-1]` is full:
-        //   (a). if the last superblock `SB[s-1]` is full:
-        if self.sb_cap == self.sb_len {
-            // i. increment `s`
-            self.superblock += 1;
-            self.sb_len = 0;
-            // ii. if `s` is odd, double the number of data block in a superblock
-            if self.superblock % 2 == 0 {
-                self.sb_cap *= 2;
-            // iii. otherwise, double the number of elements in a data block.
-            } else {
-                self.block_cap *= 2;
-            }
-        }
-
-        //   (b). if there are no empty data blocks:
-        self.sb_len += 1;
-    }
-}
-
-// === impl Block ==
-
-impl<T> Block<T> {
-    fn new(capacity: usize) -> Self {
-        let elements = Vec::with_capacity(capacity);
-        debug_assert_eq!(capacity, elements.capacity());
-        Self { elements }
-    }
-
-    fn is_full(&self) -> bool {
-        self.elements.capacity() == self.elements.len()
-    }
-
-    fn push(&mut self, element: T) {
-        debug_assert!(!self.is_full(), "Block vectors should never reallocate");
-        self.elements.push(element);
-    }
-}
-
-impl<T: fmt::Debug> fmt::Debug for Block<T> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("Block")
-            .field("len", &self.elements.len())
-            .field("capacity", &self.elements.capacity())
-            .field("elements", &self.elements)
-            .finish()
-    }
-}
-
-// === impl DebugDetails ===
-
-#[cfg(test)]
-impl<T: fmt::Debug> fmt::Debug for DebugDetails<'_, T> {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut f = f.debug_struct("SegVec");
-        f.field("meta", &self.0.meta)
-            .field("capacity", &self.0.capacity)
-            .field("index", &self.0.index);
-        #[cfg(debug_assertions)]
-        {
-            f.field("is_initialized", &self.0.is_initialized);
+    /// All the data blocks prior to that one are skipped.
+    fn skip_to<P: GrowthPolicy>(capacity: usize) -> Self {
+        debug_assert!(capacity.is_power_of_two());
+        test_dbg!(let skipped_blocks = P::first_block(capacity););
+        Self {
+            skipped_blocks,
+            skipped_indices: P::block_start(skipped_blocks),
+            ..Self::empty()
         }
-        f.finish()
     }
 }
 
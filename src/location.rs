@@ -0,0 +1,108 @@
+//! Locating slots in Brodnik et al.'s layout of superblocks and data blocks.
+//!
+//! Unlike `FixedIndex`, this doesn't need atomics, so it's available on every
+//! target.
+
+/// The location of a slot in Brodnik et al.'s layout, as used by
+/// `FixedIndex`.
+///
+/// This is also used by the other segmented vectors, which share the same
+/// layout. A [`SegVec`](crate::SegVec) may skip the low-size data blocks, so
+/// it must compensate for those.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Location {
+    pub(crate) superblock: usize,
+    /// The index of the data block within its superblock.
+    pub(crate) block: usize,
+    /// The index of the slot within its data block.
+    pub(crate) slot: usize,
+}
+
+// === impl Location ===
+
+impl Location {
+    /// Returns the location of the slot at index `i`.
+    ///
+    /// This is the `locate` algorithm from the paper, without any skipped
+    /// blocks.
+    #[inline]
+    pub(crate) fn of(i: usize) -> Self {
+        // The binary representation of `i + 1` is `1bbbeee`, where `bbb` is
+        // the index of the data block within the superblock, and `eee` is the
+        // index of the slot within the data block.
+        let r = i.checked_add(1).expect("FixedIndex index overflow");
+        let superblock = (usize::BITS - 1 - r.leading_zeros()) as usize;
+        let slot_bits = (superblock + 1) >> 1;
+        let block_bits = superblock / 2;
+        Self {
+            superblock,
+            block: (r >> slot_bits) & !(usize::MAX << block_bits),
+            slot: r & !(usize::MAX << slot_bits),
+        }
+    }
+
+    /// Returns the location of the first slot in the data block at index
+    /// `data_block`, counting the data blocks in every earlier superblock.
+    ///
+    /// This is the inverse of [`Location::data_block`].
+    #[inline]
+    pub(crate) fn of_data_block(data_block: usize) -> Self {
+        // The first data block of superblock `2k` is `2^(k+1) - 2`, and the
+        // first data block of superblock `2k + 1` is `3 * 2^k - 2`. So with
+        // `x = data_block + 2`, the bit length of `x` gives `k`, and the bit
+        // after its leading one tells the two superblocks apart.
+        let x = data_block + 2;
+        let k = (usize::BITS - 2 - x.leading_zeros()) as usize;
+        let superblock = 2 * k + ((x >> k) & 1);
+        let first_block = Self::block_len(superblock) + Self::blocks_in(superblock) - 2;
+        Self {
+            superblock,
+            block: data_block - first_block,
+            slot: 0,
+        }
+    }
+
+    /// Returns the location of the first slot in the data block following
+    /// this slot's data block.
+    pub(crate) fn next_block(self) -> Self {
+        if self.block + 1 == Self::blocks_in(self.superblock) {
+            Self {
+                superblock: self.superblock + 1,
+                block: 0,
+                slot: 0,
+            }
+        } else {
+            Self {
+                superblock: self.superblock,
+                block: self.block + 1,
+                slot: 0,
+            }
+        }
+    }
+
+    /// Returns the index of this slot's data block, counting the data blocks
+    /// in every earlier superblock.
+    #[inline]
+    pub(crate) fn data_block(&self) -> usize {
+        Self::block_len(self.superblock) + Self::blocks_in(self.superblock) - 2 + self.block
+    }
+
+    /// Returns the index of the first slot in data block `block` of
+    /// `superblock`.
+    #[inline]
+    pub(crate) const fn start_of(superblock: usize, block: usize) -> usize {
+        (1 << superblock) - 1 + block * Self::block_len(superblock)
+    }
+
+    /// Returns the number of data blocks in `superblock`.
+    #[inline]
+    pub(crate) const fn blocks_in(superblock: usize) -> usize {
+        1 << (superblock / 2)
+    }
+
+    /// Returns the number of slots in each of `superblock`'s data blocks.
+    #[inline]
+    pub(crate) const fn block_len(superblock: usize) -> usize {
+        1 << ((superblock + 1) >> 1)
+    }
+}
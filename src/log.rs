@@ -0,0 +1,346 @@
+//! Single-writer, multi-reader append logs.
+//!
+//! A `SegVec` can be turned into a [`Writer`] and a [`Reader`] using
+//! [`SegVec::into_log`]. See that method for details.
+use crate::{fixed_index::FixedIndex, location::Location, Global, IndexBlock, SegVec};
+use alloc::sync::Arc;
+use core::{
+    cell::UnsafeCell,
+    fmt,
+    mem::{self, MaybeUninit},
+    sync::atomic::{AtomicUsize, Ordering::*},
+};
+
+/// The writing half of an append log.
+///
+/// There is only ever one `Writer` for a log. It appends elements with
+/// [`push`](Self::push), which are visible to every [`Reader`] once `push`
+/// returns.
+///
+/// This is returned by [`SegVec::into_log`].
+pub struct Writer<T> {
+    log: Arc<Log<T>>,
+}
+
+/// A reading handle to an append log.
+///
+/// A `Reader` can access every element below the log's published
+/// [`len`](Self::len), while the [`Writer`] keeps appending to it. Readers can
+/// be cloned and sent to other threads.
+///
+/// This is returned by [`SegVec::into_log`].
+pub struct Reader<T> {
+    log: Arc<Log<T>>,
+}
+
+/// An iterator over the elements of an append log.
+///
+/// This is returned by [`Reader::iter`] and [`Writer::iter`].
+#[derive(Debug)]
+pub struct Iter<'log, T> {
+    log: &'log Log<T>,
+    idx: usize,
+    len: usize,
+}
+
+/// The state shared by a log's `Writer` and `Reader`s.
+struct Log<T> {
+    /// The number of elements which have been written, and may be read.
+    published: AtomicUsize,
+
+    /// The number of slots in the data blocks that the `SegVec` skipped,
+    /// which the log's element indices are offset by.
+    offset: usize,
+
+    index: FixedIndex<Slot<T>>,
+}
+
+/// A slot in the log. This has the same layout as a `T`, so that a
+/// `SegVec`'s data blocks can be used as the log's data blocks.
+#[repr(transparent)]
+struct Slot<T>(UnsafeCell<MaybeUninit<T>>);
+
+// Safety: elements are written by the `Writer` and read by any number of
+// `Reader`s on other threads, so they must be `Sync`; they may be dropped by
+// whichever thread drops the last handle, so they must also be `Send`. A slot
+// is only written by the `Writer`, before it's published, and is never
+// written again.
+unsafe impl<T: Send + Sync> Send for Log<T> {}
+unsafe impl<T: Send + Sync> Sync for Log<T> {}
+
+impl<T> SegVec<T> {
+    /// Converts this `SegVec` into a single-writer, multi-reader append log.
+    ///
+    /// The [`Writer`] appends elements to the log, and [`Reader`]s can read
+    /// any element which has been appended, from any thread, while the writer
+    /// keeps appending. Because data blocks never move, a `Reader` can hold
+    /// references to elements while the log grows.
+    ///
+    /// The elements of the `SegVec` become the first elements of the log.
+    /// The `SegVec`'s data blocks become the log's first data blocks, so no
+    /// elements are moved, and the log keeps using any spare capacity the
+    /// `SegVec` had. Converting takes time proportional to the number of data
+    /// blocks, rather than the number of elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use segvec::SegVec;
+    /// use std::thread;
+    ///
+    /// let mut segvec = SegVec::new();
+    /// segvec.push("hello");
+    /// let (mut writer, reader) = segvec.into_log();
+    ///
+    /// let first = reader.get(0).unwrap();
+    /// thread::scope(|scope| {
+    ///     scope.spawn(|| {
+    ///         for _ in 0..100 {
+    ///             writer.push("world");
+    ///         }
+    ///     });
+    ///     scope.spawn(|| {
+    ///         // The reader sees every element below the published length.
+    ///         let len = reader.len();
+    ///         assert!(reader.iter().skip(1).take(len - 1).all(|&s| s == "world"));
+    ///     });
+    /// });
+    ///
+    /// assert_eq!(*first, "hello");
+    /// assert_eq!(reader.len(), 101);
+    /// ```
+    pub fn into_log(mut self) -> (Writer<T>, Reader<T>) {
+        // Take the elements and data blocks away from the `SegVec`. If
+        // building the log panics, they're leaked, rather than dropped twice.
+        let len = self.len();
+        // Safety: the `SegVec` no longer owns any elements.
+        unsafe { self.set_len(0) };
+        // An `IndexBlock` doesn't own its data blocks, so dropping it only
+        // frees the array of pointers.
+        let blocks = mem::replace(&mut self.index, IndexBlock::new_in(Global));
+
+        let mut log = Log {
+            published: AtomicUsize::new(0),
+            offset: self.meta.skipped_indices,
+            index: FixedIndex::new(),
+        };
+
+        if Self::IS_ZST {
+            // A `SegVec` of zero-sized elements has no data blocks, so
+            // allocate (dangling) data blocks in the log for its elements.
+            let mut idx = 0;
+            while idx < len {
+                log.index.get_or_alloc(idx);
+                let next = Location::of(idx).next_block();
+                idx = Location::start_of(next.superblock, next.block);
+            }
+        }
+
+        for (block, &ptr) in blocks.iter().enumerate() {
+            // Safety: the `SegVec`'s data blocks were allocated by the global
+            // allocator, with the capacity of their data block in the Brodnik
+            // layout, and the `SegVec` no longer owns them. A `Slot<T>` has
+            // the same layout as a `T`, and slots don't need to be
+            // initialized.
+            unsafe {
+                log.index
+                    .adopt_block(block + self.meta.skipped_blocks, ptr.cast())
+            };
+        }
+        *log.published.get_mut() = len;
+
+        let log = Arc::new(log);
+        let reader = Reader { log: log.clone() };
+        (Writer { log }, reader)
+    }
+}
+
+// === impl Writer ===
+
+impl<T> Writer<T> {
+    /// Appends an element to the log, returning its index.
+    ///
+    /// The element is visible to every [`Reader`] once this method returns.
+    ///
+    /// # Panics
+    ///
+    /// If allocating a new data block fails.
+    pub fn push(&mut self, value: T) -> usize {
+        // Only the writer changes the published length, so it can't change
+        // while we're writing.
+        let idx = self.log.published.load(Relaxed);
+        // Safety: we are the only writer, and the slot at `idx` hasn't been
+        // published, so no readers will access it.
+        unsafe { self.log.write(idx, value) };
+        self.log.published.store(idx + 1, Release);
+        idx
+    }
+
+    /// Returns a new [`Reader`] for this log.
+    pub fn reader(&self) -> Reader<T> {
+        Reader {
+            log: self.log.clone(),
+        }
+    }
+
+    /// Returns the number of elements in the log.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.log.len()
+    }
+
+    /// Returns `true` if the log contains no elements.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns a reference to the element at `idx`, or `None` if it is out of
+    /// bounds.
+    pub fn get(&self, idx: usize) -> Option<&T> {
+        self.log.get(idx)
+    }
+
+    /// Returns an iterator over the elements in the log.
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.log.iter()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Writer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+// === impl Reader ===
+
+impl<T> Reader<T> {
+    /// Returns the number of elements which have been published to the log.
+    ///
+    /// Every index below the returned length can be read. The length only
+    /// increases, as the [`Writer`] appends elements.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.log.len()
+    }
+
+    /// Returns `true` if no elements have been published to the log.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns a reference to the element at `idx`, or `None` if it hasn't
+    /// been published yet.
+    pub fn get(&self, idx: usize) -> Option<&T> {
+        self.log.get(idx)
+    }
+
+    /// Returns an iterator over the elements which had been published to the
+    /// log when this method was called.
+    pub fn iter(&self) -> Iter<'_, T> {
+        self.log.iter()
+    }
+}
+
+impl<T> Clone for Reader<T> {
+    fn clone(&self) -> Self {
+        Self {
+            log: self.log.clone(),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Reader<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+// === impl Log ===
+
+impl<T> Log<T> {
+    /// Writes `value` to the slot at `idx`.
+    ///
+    /// # Safety
+    ///
+    /// The caller must be the log's only writer, and `idx` must be the
+    /// published length.
+    unsafe fn write(&self, idx: usize, value: T) {
+        let slot = self.index.get_or_alloc(idx + self.offset);
+        // Safety: guaranteed by the caller.
+        unsafe { (*slot.0.get()).write(value) };
+    }
+
+    fn len(&self) -> usize {
+        self.published.load(Acquire)
+    }
+
+    fn get(&self, idx: usize) -> Option<&T> {
+        if idx >= self.len() {
+            return None;
+        }
+
+        let slot = self.index.get(idx + self.offset)?;
+        // Safety: the slot was written before it was published, and will
+        // never be written again.
+        Some(unsafe { (*slot.0.get()).assume_init_ref() })
+    }
+
+    fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            log: self,
+            idx: 0,
+            len: self.len(),
+        }
+    }
+}
+
+impl<T> Drop for Log<T> {
+    fn drop(&mut self) {
+        for idx in 0..*self.published.get_mut() {
+            if let Some(slot) = self.index.get_mut(idx + self.offset) {
+                // Safety: every published slot is initialized.
+                unsafe { slot.0.get_mut().assume_init_drop() };
+            }
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Log<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+// === impl Iter ===
+
+impl<'log, T> Iterator for Iter<'log, T> {
+    type Item = &'log T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.idx == self.len {
+            return None;
+        }
+
+        let elem = self.log.get(self.idx);
+        self.idx += 1;
+        elem
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len - self.idx;
+        (len, Some(len))
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+// === impl Slot ===
+
+impl<T> Default for Slot<T> {
+    fn default() -> Self {
+        Self(UnsafeCell::new(MaybeUninit::uninit()))
+    }
+}
@@ -0,0 +1,149 @@
+//! A concurrent, sparse vector of slots which can each be initialized once.
+//!
+//! See [`OnceSegVec`] for details.
+use crate::fixed_index::{self, FixedIndex};
+use core::fmt;
+use std::sync::OnceLock;
+
+/// A concurrent, sparse vector of slots which can each be initialized once.
+///
+/// A `OnceSegVec<T>` behaves like an infinitely long `Vec<OnceLock<T>>`: any
+/// index may be initialized, from any thread, through a shared reference, and
+/// once a slot is initialized, its value never changes. The data block
+/// containing an index is only allocated when a slot in it is initialized, so
+/// initializing a slot doesn't require allocating the slots before it.
+///
+/// This is useful for memoization caches which are shared between threads.
+///
+/// This type is only available when the `std` feature flag is enabled.
+///
+/// # Examples
+///
+/// ```
+/// use segvec::OnceSegVec;
+/// use std::thread;
+///
+/// fn fib(cache: &OnceSegVec<u64>, n: usize) -> u64 {
+///     if n < 2 {
+///         return n as u64;
+///     }
+///     *cache.get_or_init(n, || fib(cache, n - 1) + fib(cache, n - 2))
+/// }
+///
+/// let cache = OnceSegVec::new();
+/// thread::scope(|scope| {
+///     for _ in 0..4 {
+///         scope.spawn(|| assert_eq!(fib(&cache, 90), 2_880_067_194_370_816_120));
+///     }
+/// });
+///
+/// assert_eq!(cache.get(10), Some(&55));
+/// assert_eq!(cache.get(1000), None);
+/// ```
+pub struct OnceSegVec<T> {
+    index: FixedIndex<OnceLock<T>>,
+}
+
+/// An iterator over the initialized slots of a [`OnceSegVec`], along with
+/// their indices.
+///
+/// This is returned by [`OnceSegVec::iter`].
+pub struct Iter<'segvec, T> {
+    slots: fixed_index::Iter<'segvec, OnceLock<T>>,
+}
+
+impl<T> OnceSegVec<T> {
+    /// Constructs a new `OnceSegVec`, with every slot uninitialized.
+    ///
+    /// The `OnceSegVec` will not allocate until a slot is initialized.
+    pub const fn new() -> Self {
+        Self {
+            index: FixedIndex::new(),
+        }
+    }
+
+    /// Returns a reference to the value of the slot at `idx`, or `None` if it
+    /// hasn't been initialized.
+    pub fn get(&self, idx: usize) -> Option<&T> {
+        self.index.get(idx)?.get()
+    }
+
+    /// Returns a mutable reference to the value of the slot at `idx`, or
+    /// `None` if it hasn't been initialized.
+    pub fn get_mut(&mut self, idx: usize) -> Option<&mut T> {
+        self.index.get_mut(idx)?.get_mut()
+    }
+
+    /// Initializes the slot at `idx` to `value`.
+    ///
+    /// If the slot was already initialized, `Err(value)` is returned.
+    ///
+    /// # Panics
+    ///
+    /// If `idx` is `usize::MAX`, or if allocating the slot's data block
+    /// fails.
+    pub fn set(&self, idx: usize, value: T) -> Result<(), T> {
+        self.index.get_or_alloc(idx).set(value)
+    }
+
+    /// Returns a reference to the value of the slot at `idx`, initializing it
+    /// with `f` if it hasn't been initialized.
+    ///
+    /// Like [`OnceLock::get_or_init`], if several threads try to initialize
+    /// the same slot concurrently, only one of them will run `f`, and the
+    /// others will block until it's done.
+    ///
+    /// # Panics
+    ///
+    /// If `idx` is `usize::MAX`, or if allocating the slot's data block
+    /// fails. If `f` panics, the panic is propagated, and the slot remains
+    /// uninitialized.
+    pub fn get_or_init(&self, idx: usize, f: impl FnOnce() -> T) -> &T {
+        self.index.get_or_alloc(idx).get_or_init(f)
+    }
+
+    /// Returns an iterator over the initialized slots, along with their
+    /// indices, in index order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            slots: self.index.iter(),
+        }
+    }
+}
+
+impl<T> Default for OnceSegVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for OnceSegVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<'segvec, T> IntoIterator for &'segvec OnceSegVec<T> {
+    type IntoIter = Iter<'segvec, T>;
+    type Item = (usize, &'segvec T);
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+// === impl Iter ===
+
+impl<'segvec, T> Iterator for Iter<'segvec, T> {
+    type Item = (usize, &'segvec T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.slots.find_map(|(idx, slot)| Some((idx, slot.get()?)))
+    }
+}
+
+impl<T> fmt::Debug for Iter<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Iter").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,721 @@
+//! Parallel iterators over a `SegVec`, using [`rayon`].
+//!
+//! This module is available when the `rayon` feature flag is enabled. It
+//! implements [`IntoParallelIterator`] for `&SegVec`, `&mut SegVec`, and
+//! `SegVec`, so the [`par_iter`] and [`par_iter_mut`] methods are available on
+//! a `SegVec`. [`ParallelExtend`] and [`FromParallelIterator`] are also
+//! implemented.
+//!
+//! When a parallel iterator over a `SegVec` is split into smaller pieces of
+//! work, it is split on data block boundaries where possible, and only splits
+//! a data block once the range of elements lies within a single block. This
+//! means that each piece of work iterates over contiguous slices of memory.
+//!
+//! [`rayon`]: https://docs.rs/rayon
+//! [`par_iter`]: rayon::iter::IntoParallelRefIterator::par_iter
+//! [`par_iter_mut`]: rayon::iter::IntoParallelRefMutIterator::par_iter_mut
+use crate::{Allocator, GrowthPolicy, SegVec};
+use alloc::{collections::LinkedList, vec::Vec};
+use core::{cmp, iter, marker::PhantomData, mem, ptr, ptr::NonNull, slice};
+use rayon::iter::{
+    plumbing::{
+        bridge, bridge_unindexed, Consumer, Folder, Producer, ProducerCallback, UnindexedConsumer,
+        UnindexedProducer,
+    },
+    FromParallelIterator, IndexedParallelIterator, IntoParallelIterator, ParallelExtend,
+    ParallelIterator,
+};
+
+/// A parallel iterator over references to the elements of a [`SegVec`].
+///
+/// This is returned by [`par_iter`](rayon::iter::IntoParallelRefIterator::par_iter).
+#[derive(Debug)]
+pub struct ParIter<'segvec, T, A: Allocator, P: GrowthPolicy> {
+    segvec: &'segvec SegVec<T, A, P>,
+}
+
+/// A parallel iterator over mutable references to the elements of a
+/// [`SegVec`].
+///
+/// This is returned by
+/// [`par_iter_mut`](rayon::iter::IntoParallelRefMutIterator::par_iter_mut).
+#[derive(Debug)]
+pub struct ParIterMut<'segvec, T, A: Allocator, P: GrowthPolicy> {
+    segvec: &'segvec mut SegVec<T, A, P>,
+}
+
+/// An owning parallel iterator over the elements of a [`SegVec`].
+///
+/// This is returned by [`into_par_iter`](IntoParallelIterator::into_par_iter).
+#[derive(Debug)]
+pub struct IntoParIter<T, A: Allocator, P: GrowthPolicy> {
+    segvec: SegVec<T, A, P>,
+}
+
+/// A range of elements in a `SegVec`.
+///
+/// This is the shared implementation of the producers for all three parallel
+/// iterators, so it deals in raw pointers: whether those pointers are turned
+/// into shared references, mutable references, or owned values is up to the
+/// producer.
+///
+/// It borrows only the `SegVec`'s index and copies its layout metadata, never
+/// the `SegVec` itself, so sending a range to another thread doesn't share the
+/// `SegVec`'s allocator.
+struct RawRange<'segvec, T, P: GrowthPolicy> {
+    /// The pointers to the `SegVec`'s data blocks.
+    blocks: &'segvec [NonNull<T>],
+    skipped_blocks: usize,
+    skipped_indices: usize,
+    start: usize,
+    end: usize,
+    _policy: PhantomData<fn() -> P>,
+}
+
+/// A sequential iterator over a `RawRange`, yielding pointers to each element.
+struct RawIter<'segvec, T, P: GrowthPolicy> {
+    range: RawRange<'segvec, T, P>,
+    /// The unvisited part of the data block containing `range.start`.
+    front: Run<T>,
+    /// The unvisited part of the data block containing `range.end - 1`, in
+    /// reverse.
+    back: Run<T>,
+}
+
+/// A contiguous run of elements within a data block.
+struct Run<T> {
+    ptr: *mut T,
+    len: usize,
+}
+
+struct RefProducer<'segvec, T, P: GrowthPolicy> {
+    range: RawRange<'segvec, T, P>,
+}
+
+struct MutProducer<'segvec, T, P: GrowthPolicy> {
+    range: RawRange<'segvec, T, P>,
+    _elements: PhantomData<&'segvec mut T>,
+}
+
+/// A producer which owns the elements in its range, dropping any that it
+/// doesn't produce.
+struct DrainProducer<'segvec, T, P: GrowthPolicy> {
+    range: RawRange<'segvec, T, P>,
+}
+
+/// A sequential iterator which owns the elements in its range, dropping any
+/// that it doesn't yield.
+struct DrainIter<'segvec, T, P: GrowthPolicy> {
+    iter: RawIter<'segvec, T, P>,
+}
+
+type RefIter<'segvec, T, P> = iter::Map<RawIter<'segvec, T, P>, fn(*mut T) -> &'segvec T>;
+type MutIter<'segvec, T, P> = iter::Map<RawIter<'segvec, T, P>, fn(*mut T) -> &'segvec mut T>;
+
+// === impl SegVec ===
+
+impl<'segvec, T: Sync, A: Allocator + Sync, P: GrowthPolicy> IntoParallelIterator
+    for &'segvec SegVec<T, A, P>
+{
+    type Iter = ParIter<'segvec, T, A, P>;
+    type Item = &'segvec T;
+
+    fn into_par_iter(self) -> Self::Iter {
+        ParIter { segvec: self }
+    }
+}
+
+impl<'segvec, T: Send, A: Allocator + Send, P: GrowthPolicy> IntoParallelIterator
+    for &'segvec mut SegVec<T, A, P>
+{
+    type Iter = ParIterMut<'segvec, T, A, P>;
+    type Item = &'segvec mut T;
+
+    fn into_par_iter(self) -> Self::Iter {
+        ParIterMut { segvec: self }
+    }
+}
+
+impl<T: Send, A: Allocator + Send, P: GrowthPolicy> IntoParallelIterator for SegVec<T, A, P> {
+    type Iter = IntoParIter<T, A, P>;
+    type Item = T;
+
+    fn into_par_iter(self) -> Self::Iter {
+        IntoParIter { segvec: self }
+    }
+}
+
+impl<T: Send, A: Allocator, P: GrowthPolicy> ParallelExtend<T> for SegVec<T, A, P> {
+    fn par_extend<I>(&mut self, par_iter: I)
+    where
+        I: IntoParallelIterator<Item = T>,
+    {
+        // Collect the elements into a list of `Vec`s, one per piece of work,
+        // so that we know how many there are before moving them into the
+        // `SegVec`. This way, we only need to grow the `SegVec` once.
+        let list = par_iter
+            .into_par_iter()
+            .fold(Vec::new, |mut vec, elem| {
+                vec.push(elem);
+                vec
+            })
+            .map(|vec| {
+                let mut list = LinkedList::new();
+                list.push_back(vec);
+                list
+            })
+            .reduce(LinkedList::new, |mut a, mut b| {
+                a.append(&mut b);
+                a
+            });
+
+        self.reserve(list.iter().map(Vec::len).sum());
+        for vec in list {
+            self.extend(vec);
+        }
+    }
+}
+
+impl<T: Send> FromParallelIterator<T> for SegVec<T> {
+    fn from_par_iter<I>(par_iter: I) -> Self
+    where
+        I: IntoParallelIterator<Item = T>,
+    {
+        let mut segvec = SegVec::new();
+        segvec.par_extend(par_iter);
+        segvec
+    }
+}
+
+// === impl ParIter ===
+
+impl<'segvec, T: Sync, A: Allocator + Sync, P: GrowthPolicy> ParallelIterator
+    for ParIter<'segvec, T, A, P>
+{
+    type Item = &'segvec T;
+
+    // Note that this doesn't override `opt_len`: if it returned a length,
+    // rayon would require `drive_unindexed` to split the iterator at the
+    // indices that the consumer chooses, rather than on block boundaries.
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        let range = RawRange::new(self.segvec, 0, self.segvec.len());
+        bridge_unindexed(RefProducer { range }, consumer)
+    }
+}
+
+impl<T: Sync, A: Allocator + Sync, P: GrowthPolicy> IndexedParallelIterator
+    for ParIter<'_, T, A, P>
+{
+    fn drive<C: Consumer<Self::Item>>(self, consumer: C) -> C::Result {
+        bridge(self, consumer)
+    }
+
+    fn len(&self) -> usize {
+        self.segvec.len()
+    }
+
+    fn with_producer<CB: ProducerCallback<Self::Item>>(self, callback: CB) -> CB::Output {
+        let range = RawRange::new(self.segvec, 0, self.segvec.len());
+        callback.callback(RefProducer { range })
+    }
+}
+
+// === impl ParIterMut ===
+
+impl<'segvec, T: Send, A: Allocator + Send, P: GrowthPolicy> ParallelIterator
+    for ParIterMut<'segvec, T, A, P>
+{
+    type Item = &'segvec mut T;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        bridge_unindexed(MutProducer::new(self.segvec), consumer)
+    }
+}
+
+impl<T: Send, A: Allocator + Send, P: GrowthPolicy> IndexedParallelIterator
+    for ParIterMut<'_, T, A, P>
+{
+    fn drive<C: Consumer<Self::Item>>(self, consumer: C) -> C::Result {
+        bridge(self, consumer)
+    }
+
+    fn len(&self) -> usize {
+        self.segvec.len()
+    }
+
+    fn with_producer<CB: ProducerCallback<Self::Item>>(self, callback: CB) -> CB::Output {
+        callback.callback(MutProducer::new(self.segvec))
+    }
+}
+
+// === impl IntoParIter ===
+
+impl<T: Send, A: Allocator + Send, P: GrowthPolicy> IntoParIter<T, A, P> {
+    /// Transfers ownership of all of the `SegVec`'s elements to a
+    /// `DrainProducer`.
+    fn drain(&mut self) -> DrainProducer<'_, T, P> {
+        let len = self.segvec.len();
+        // Empty the `SegVec` without dropping its elements, so that dropping
+        // it only deallocates the blocks. The producer (or, once it's split,
+        // the producers) will drop any elements that aren't consumed. Note
+        // that the `SegVec`'s metadata still describes where its elements
+        // are, so we can still locate them.
+        // Safety: the producer takes ownership of the elements.
+        unsafe { self.segvec.set_len(0) };
+        DrainProducer {
+            range: RawRange::new(&self.segvec, 0, len),
+        }
+    }
+}
+
+impl<T: Send, A: Allocator + Send, P: GrowthPolicy> ParallelIterator for IntoParIter<T, A, P> {
+    type Item = T;
+
+    fn drive_unindexed<C>(mut self, consumer: C) -> C::Result
+    where
+        C: UnindexedConsumer<Self::Item>,
+    {
+        bridge_unindexed(self.drain(), consumer)
+    }
+}
+
+impl<T: Send, A: Allocator + Send, P: GrowthPolicy> IndexedParallelIterator
+    for IntoParIter<T, A, P>
+{
+    fn drive<C: Consumer<Self::Item>>(self, consumer: C) -> C::Result {
+        bridge(self, consumer)
+    }
+
+    fn len(&self) -> usize {
+        self.segvec.len()
+    }
+
+    fn with_producer<CB: ProducerCallback<Self::Item>>(mut self, callback: CB) -> CB::Output {
+        callback.callback(self.drain())
+    }
+}
+
+// === impl RawRange ===
+
+impl<'segvec, T, P: GrowthPolicy> RawRange<'segvec, T, P> {
+    const IS_ZST: bool = mem::size_of::<T>() == 0;
+
+    fn new<A: Allocator>(segvec: &'segvec SegVec<T, A, P>, start: usize, end: usize) -> Self {
+        debug_assert!(start <= end);
+        // A drained `SegVec` has a length of zero, but its elements are still
+        // in its data blocks.
+        debug_assert!(end <= segvec.capacity());
+        Self {
+            blocks: &segvec.index,
+            skipped_blocks: segvec.meta.skipped_blocks,
+            skipped_indices: segvec.meta.skipped_indices,
+            start,
+            end,
+            _policy: PhantomData,
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Splits the range at `mid`, relative to the start of the range.
+    fn split_at(self, mid: usize) -> (Self, Self) {
+        debug_assert!(mid <= self.len());
+        let mid = self.start + mid;
+        (Self { end: mid, ..self }, Self { start: mid, ..self })
+    }
+
+    /// Returns the data block containing the element at `i`, and the
+    /// element's index within it, like `SegVec::locate`.
+    fn locate(&self, i: usize) -> (usize, usize) {
+        if Self::IS_ZST {
+            return (0, i);
+        }
+
+        let (block, idx) = P::locate(i + self.skipped_indices);
+        (block - self.skipped_blocks, idx)
+    }
+
+    /// Returns the capacity of the data block at position `block`, like
+    /// `SegVec::block_capacity`.
+    fn block_capacity(&self, block: usize) -> usize {
+        if Self::IS_ZST {
+            return usize::MAX;
+        }
+
+        P::block_capacity(block + self.skipped_blocks)
+    }
+
+    /// Returns the pointer to the data block at position `block`, like
+    /// `SegVec::block_ptr`.
+    fn block_ptr(&self, block: usize) -> NonNull<T> {
+        if Self::IS_ZST {
+            return NonNull::dangling();
+        }
+
+        self.blocks[block]
+    }
+
+    /// Splits the range on the data block boundary closest to its midpoint,
+    /// or at its midpoint, if it lies within a single data block.
+    ///
+    /// Returns `None` if the range is too short to split.
+    fn split(self) -> Option<(Self, Self)> {
+        if self.len() < 2 {
+            return None;
+        }
+
+        let mid = self.start + self.len() / 2;
+        let (block, idx) = self.locate(mid);
+        let block_start = mid - idx;
+        let block_end = block_start + self.block_capacity(block);
+
+        // The block boundaries on either side of the midpoint, if they're
+        // inside the range.
+        let before = Some(block_start).filter(|&at| at > self.start);
+        let after = Some(block_end).filter(|&at| at < self.end);
+        let at = match (before, after) {
+            (Some(before), Some(after)) if after - mid < mid - before => after,
+            (Some(before), _) => before,
+            (None, Some(after)) => after,
+            (None, None) => mid,
+        };
+        Some(self.split_at(at - self.start))
+    }
+
+    /// Returns the run of elements starting at `self.start`, up to the end of
+    /// its data block or the end of the range.
+    fn front_run(&self) -> Run<T> {
+        let (block, idx) = self.locate(self.start);
+        Run {
+            // Safety: `idx` is in bounds of the block's allocation.
+            ptr: unsafe { self.block_ptr(block).as_ptr().add(idx) },
+            len: cmp::min(self.len(), self.block_capacity(block) - idx),
+        }
+    }
+
+    /// Returns the run of elements ending at `self.end - 1`, back to the start
+    /// of its data block or the start of the range. The run's pointer points
+    /// to its _last_ element.
+    fn back_run(&self) -> Run<T> {
+        let (block, idx) = self.locate(self.end - 1);
+        Run {
+            // Safety: `idx` is in bounds of the block's allocation.
+            ptr: unsafe { self.block_ptr(block).as_ptr().add(idx) },
+            len: cmp::min(self.len(), idx + 1),
+        }
+    }
+
+    /// Returns an iterator over each contiguous run of elements in the range,
+    /// from front to back.
+    fn runs(mut self) -> impl Iterator<Item = Run<T>> + 'segvec
+    where
+        P: 'segvec,
+    {
+        iter::from_fn(move || {
+            if self.start == self.end {
+                return None;
+            }
+
+            let run = self.front_run();
+            self.start += run.len;
+            Some(run)
+        })
+    }
+
+    fn into_iter(self) -> RawIter<'segvec, T, P> {
+        RawIter {
+            range: self,
+            front: Run::EMPTY,
+            back: Run::EMPTY,
+        }
+    }
+}
+
+impl<T, P: GrowthPolicy> Clone for RawRange<'_, T, P> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, P: GrowthPolicy> Copy for RawRange<'_, T, P> {}
+
+// === impl RawIter ===
+
+impl<T, P: GrowthPolicy> Iterator for RawIter<'_, T, P> {
+    type Item = *mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.range.start == self.range.end {
+            return None;
+        }
+
+        // Note that if the back of the iterator has already visited some of
+        // this run, then the range will end before the run does.
+        if self.front.len == 0 {
+            self.front = self.range.front_run();
+        }
+        let ptr = self.front.ptr;
+        self.front.ptr = self.front.ptr.wrapping_add(1);
+        self.front.len -= 1;
+        self.range.start += 1;
+        Some(ptr)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.range.len();
+        (len, Some(len))
+    }
+}
+
+impl<T, P: GrowthPolicy> DoubleEndedIterator for RawIter<'_, T, P> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.range.start == self.range.end {
+            return None;
+        }
+
+        if self.back.len == 0 {
+            self.back = self.range.back_run();
+        }
+        let ptr = self.back.ptr;
+        self.back.ptr = self.back.ptr.wrapping_sub(1);
+        self.back.len -= 1;
+        self.range.end -= 1;
+        Some(ptr)
+    }
+}
+
+impl<T, P: GrowthPolicy> ExactSizeIterator for RawIter<'_, T, P> {}
+
+impl<T> Run<T> {
+    const EMPTY: Self = Self {
+        ptr: ptr::null_mut(),
+        len: 0,
+    };
+}
+
+// === impl RefProducer ===
+
+// Safety: a `RefProducer` only provides shared access to the elements in its
+// range. Its range only reads the data block pointers in the `SegVec`'s index,
+// which every producer split from it shares, and never the `SegVec` or its
+// allocator.
+unsafe impl<T: Sync, P: GrowthPolicy> Send for RefProducer<'_, T, P> {}
+
+impl<'segvec, T: Sync, P: GrowthPolicy> Producer for RefProducer<'segvec, T, P> {
+    type Item = &'segvec T;
+    type IntoIter = RefIter<'segvec, T, P>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        // Safety: every element in the range is initialized, and the `SegVec`
+        // is borrowed for `'segvec`.
+        self.range.into_iter().map(|ptr| unsafe { &*ptr })
+    }
+
+    fn split_at(self, index: usize) -> (Self, Self) {
+        let (left, right) = self.range.split_at(index);
+        (Self { range: left }, Self { range: right })
+    }
+
+    fn fold_with<F: Folder<Self::Item>>(self, mut folder: F) -> F {
+        for run in self.range.runs() {
+            // Safety: every element in the run is initialized, and the
+            // `SegVec` is borrowed for `'segvec`.
+            let run = unsafe { slice::from_raw_parts(run.ptr, run.len) };
+            folder = folder.consume_iter(run);
+            if folder.full() {
+                break;
+            }
+        }
+        folder
+    }
+}
+
+impl<'segvec, T: Sync, P: GrowthPolicy> UnindexedProducer for RefProducer<'segvec, T, P> {
+    type Item = &'segvec T;
+
+    fn split(self) -> (Self, Option<Self>) {
+        match self.range.split() {
+            Some((left, right)) => (Self { range: left }, Some(Self { range: right })),
+            None => (self, None),
+        }
+    }
+
+    fn fold_with<F: Folder<Self::Item>>(self, folder: F) -> F {
+        Producer::fold_with(self, folder)
+    }
+}
+
+// === impl MutProducer ===
+
+// Safety: a `MutProducer` has exclusive access to the elements in its range,
+// which doesn't overlap with any other producer split from the same `SegVec`.
+// Its range only reads the data block pointers in the `SegVec`'s index, and
+// never the `SegVec` or its allocator.
+unsafe impl<T: Send, P: GrowthPolicy> Send for MutProducer<'_, T, P> {}
+
+impl<'segvec, T: Send, P: GrowthPolicy> MutProducer<'segvec, T, P> {
+    fn new<A: Allocator>(segvec: &'segvec mut SegVec<T, A, P>) -> Self {
+        // The range only reads the `SegVec`'s index through a shared
+        // reference. The elements themselves are accessed through the
+        // data blocks' pointers, which we have exclusive access to for
+        // `'segvec`.
+        let len = segvec.len();
+        Self::from_range(RawRange::new(segvec, 0, len))
+    }
+
+    fn from_range(range: RawRange<'segvec, T, P>) -> Self {
+        Self {
+            range,
+            _elements: PhantomData,
+        }
+    }
+}
+
+impl<'segvec, T: Send, P: GrowthPolicy> Producer for MutProducer<'segvec, T, P> {
+    type Item = &'segvec mut T;
+    type IntoIter = MutIter<'segvec, T, P>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        // Safety: every element in the range is initialized, and this
+        // producer has exclusive access to them for `'segvec`.
+        self.range.into_iter().map(|ptr| unsafe { &mut *ptr })
+    }
+
+    fn split_at(self, index: usize) -> (Self, Self) {
+        let (left, right) = self.range.split_at(index);
+        (Self::from_range(left), Self::from_range(right))
+    }
+
+    fn fold_with<F: Folder<Self::Item>>(self, mut folder: F) -> F {
+        for run in self.range.runs() {
+            // Safety: every element in the run is initialized, and this
+            // producer has exclusive access to them for `'segvec`.
+            let run = unsafe { slice::from_raw_parts_mut(run.ptr, run.len) };
+            folder = folder.consume_iter(run);
+            if folder.full() {
+                break;
+            }
+        }
+        folder
+    }
+}
+
+impl<'segvec, T: Send, P: GrowthPolicy> UnindexedProducer for MutProducer<'segvec, T, P> {
+    type Item = &'segvec mut T;
+
+    fn split(self) -> (Self, Option<Self>) {
+        match self.range.split() {
+            Some((left, right)) => (Self::from_range(left), Some(Self::from_range(right))),
+            None => (self, None),
+        }
+    }
+
+    fn fold_with<F: Folder<Self::Item>>(self, folder: F) -> F {
+        Producer::fold_with(self, folder)
+    }
+}
+
+// === impl DrainProducer ===
+
+// Safety: a `DrainProducer` owns the elements in its range, which doesn't
+// overlap with any other producer split from the same `SegVec`. Its range only
+// reads the data block pointers in the `SegVec`'s index, and never the
+// `SegVec` or its allocator.
+unsafe impl<T: Send, P: GrowthPolicy> Send for DrainProducer<'_, T, P> {}
+
+impl<'segvec, T: Send, P: GrowthPolicy> DrainProducer<'segvec, T, P> {
+    /// Takes the range out of this producer, without dropping its elements.
+    fn into_range(self) -> RawRange<'segvec, T, P> {
+        let range = self.range;
+        mem::forget(self);
+        range
+    }
+}
+
+impl<'segvec, T: Send, P: GrowthPolicy> Producer for DrainProducer<'segvec, T, P> {
+    type Item = T;
+    type IntoIter = DrainIter<'segvec, T, P>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        DrainIter {
+            iter: self.into_range().into_iter(),
+        }
+    }
+
+    fn split_at(self, index: usize) -> (Self, Self) {
+        let (left, right) = self.into_range().split_at(index);
+        (Self { range: left }, Self { range: right })
+    }
+}
+
+impl<T: Send, P: GrowthPolicy> UnindexedProducer for DrainProducer<'_, T, P> {
+    type Item = T;
+
+    fn split(self) -> (Self, Option<Self>) {
+        let range = self.into_range();
+        match range.split() {
+            Some((left, right)) => (Self { range: left }, Some(Self { range: right })),
+            None => (Self { range }, None),
+        }
+    }
+
+    fn fold_with<F: Folder<Self::Item>>(self, folder: F) -> F {
+        folder.consume_iter(Producer::into_iter(self))
+    }
+}
+
+impl<T, P: GrowthPolicy> Drop for DrainProducer<'_, T, P> {
+    fn drop(&mut self) {
+        drop_range(self.range);
+    }
+}
+
+// === impl DrainIter ===
+
+impl<T, P: GrowthPolicy> Iterator for DrainIter<'_, T, P> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Safety: the element is initialized, and we own it. Advancing the
+        // iterator ensures it will not be read again.
+        self.iter.next().map(|ptr| unsafe { ptr.read() })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<T, P: GrowthPolicy> DoubleEndedIterator for DrainIter<'_, T, P> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        // Safety: the element is initialized, and we own it. Advancing the
+        // iterator ensures it will not be read again.
+        self.iter.next_back().map(|ptr| unsafe { ptr.read() })
+    }
+}
+
+impl<T, P: GrowthPolicy> ExactSizeIterator for DrainIter<'_, T, P> {}
+
+impl<T, P: GrowthPolicy> Drop for DrainIter<'_, T, P> {
+    fn drop(&mut self) {
+        drop_range(self.iter.range);
+    }
+}
+
+/// Drops the elements in a range that's owned by a `DrainProducer` or
+/// `DrainIter`.
+fn drop_range<T, P: GrowthPolicy>(range: RawRange<'_, T, P>) {
+    for run in range.runs() {
+        // Safety: the elements in the range are initialized, and owned by the
+        // caller, which will not access them again.
+        unsafe { ptr::drop_in_place(ptr::slice_from_raw_parts_mut(run.ptr, run.len)) };
+    }
+}
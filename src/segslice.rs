@@ -0,0 +1,246 @@
+use crate::{Allocator, Brodnik, Global, GrowthPolicy, Iter, SegVec};
+use core::{cmp, fmt, ops::Index};
+
+/// A view into a contiguous range of elements in a [`SegVec`].
+///
+/// Because a `SegVec`'s elements are stored in multiple non-contiguous data
+/// blocks, a range of elements cannot, in general, be borrowed as a `&[T]`
+/// slice. A `SegSlice` is the next best thing: it supports indexing and
+/// iteration over a range of a `SegVec`. If all the elements in the range
+/// happen to be stored in the same data block, [`SegSlice::as_slice`] will
+/// return them as a regular slice.
+///
+/// A `SegSlice` is returned by [`SegVec::slice`], and by the [`Windows`] and
+/// [`Chunks`] iterators.
+pub struct SegSlice<'segvec, T, A: Allocator = Global, P: GrowthPolicy = Brodnik> {
+    segvec: &'segvec SegVec<T, A, P>,
+    start: usize,
+    len: usize,
+}
+
+/// An iterator over overlapping windows of a [`SegVec`].
+///
+/// This is returned by [`SegVec::windows`].
+#[derive(Debug)]
+pub struct Windows<'segvec, T, A: Allocator = Global, P: GrowthPolicy = Brodnik> {
+    segvec: &'segvec SegVec<T, A, P>,
+    pos: usize,
+    size: usize,
+}
+
+/// An iterator over non-overlapping chunks of a [`SegVec`].
+///
+/// This is returned by [`SegVec::chunks`].
+#[derive(Debug)]
+pub struct Chunks<'segvec, T, A: Allocator = Global, P: GrowthPolicy = Brodnik> {
+    segvec: &'segvec SegVec<T, A, P>,
+    pos: usize,
+    chunk_size: usize,
+}
+
+// === impl SegSlice ===
+
+impl<'segvec, T, A: Allocator, P: GrowthPolicy> SegSlice<'segvec, T, A, P> {
+    pub(crate) fn new(segvec: &'segvec SegVec<T, A, P>, start: usize, len: usize) -> Self {
+        debug_assert!(start + len <= segvec.len());
+        Self { segvec, start, len }
+    }
+
+    /// Returns the number of elements in the `SegSlice`.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the `SegSlice` contains no elements.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns a reference to the element at `idx` in the `SegSlice`, or
+    /// `None` if it is out of bounds.
+    pub fn get(&self, idx: usize) -> Option<&'segvec T> {
+        if idx >= self.len {
+            return None;
+        }
+
+        self.segvec.get(self.start + idx)
+    }
+
+    /// Returns the first element of the `SegSlice`, or `None` if it is empty.
+    pub fn first(&self) -> Option<&'segvec T> {
+        self.get(0)
+    }
+
+    /// Returns the last element of the `SegSlice`, or `None` if it is empty.
+    pub fn last(&self) -> Option<&'segvec T> {
+        self.get(self.len.checked_sub(1)?)
+    }
+
+    /// Returns an iterator over the elements in the `SegSlice`.
+    pub fn iter(&self) -> Iter<'segvec, T, P> {
+        self.segvec.range_iter(self.start, self.start + self.len)
+    }
+
+    /// Returns the elements in this `SegSlice` as a `&[T]` slice, if they are
+    /// all stored in the same data block.
+    ///
+    /// If the `SegSlice` spans more than one data block, this returns `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use segvec::SegVec;
+    ///
+    /// let mut sv = SegVec::new();
+    /// for i in 0..100 {
+    ///     sv.push(i);
+    /// }
+    ///
+    /// // `sv` grew one element at a time, so it has more than one data block.
+    /// assert_eq!(sv.slice(..).as_slice(), None);
+    /// // ...but it only has one element at index 10.
+    /// assert_eq!(sv.slice(10..11).as_slice(), Some(&[10][..]));
+    /// ```
+    pub fn as_slice(&self) -> Option<&'segvec [T]> {
+        self.segvec
+            .range_as_slice(self.start, self.start + self.len)
+    }
+}
+
+impl<T, A: Allocator, P: GrowthPolicy> Clone for SegSlice<'_, T, A, P> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T, A: Allocator, P: GrowthPolicy> Copy for SegSlice<'_, T, A, P> {}
+
+impl<T, A: Allocator, P: GrowthPolicy> Index<usize> for SegSlice<'_, T, A, P> {
+    type Output = T;
+
+    #[track_caller]
+    fn index(&self, idx: usize) -> &Self::Output {
+        match self.get(idx) {
+            None => panic!(
+                "SegSlice index out of bounds: the len is {} but the index is {}",
+                self.len, idx
+            ),
+            Some(elem) => elem,
+        }
+    }
+}
+
+impl<'segvec, T, A: Allocator, P: GrowthPolicy> IntoIterator for SegSlice<'segvec, T, A, P> {
+    type IntoIter = Iter<'segvec, T, P>;
+    type Item = &'segvec T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'segvec, T, A: Allocator, P: GrowthPolicy> IntoIterator for &'_ SegSlice<'segvec, T, A, P> {
+    type IntoIter = Iter<'segvec, T, P>;
+    type Item = &'segvec T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: fmt::Debug, A: Allocator, P: GrowthPolicy> fmt::Debug for SegSlice<'_, T, A, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+// === impl Windows ===
+
+impl<'segvec, T, A: Allocator, P: GrowthPolicy> Windows<'segvec, T, A, P> {
+    pub(crate) fn new(segvec: &'segvec SegVec<T, A, P>, size: usize) -> Self {
+        debug_assert_ne!(size, 0);
+        Self {
+            segvec,
+            pos: 0,
+            size,
+        }
+    }
+}
+
+impl<'segvec, T, A: Allocator, P: GrowthPolicy> Iterator for Windows<'segvec, T, A, P> {
+    type Item = SegSlice<'segvec, T, A, P>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len() == 0 {
+            return None;
+        }
+
+        let window = SegSlice::new(self.segvec, self.pos, self.size);
+        self.pos += 1;
+        Some(window)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len();
+        (len, Some(len))
+    }
+}
+
+impl<T, A: Allocator, P: GrowthPolicy> ExactSizeIterator for Windows<'_, T, A, P> {
+    fn len(&self) -> usize {
+        let remaining = self.segvec.len() - self.pos;
+        if self.size > remaining {
+            0
+        } else {
+            remaining - self.size + 1
+        }
+    }
+}
+
+// === impl Chunks ===
+
+impl<'segvec, T, A: Allocator, P: GrowthPolicy> Chunks<'segvec, T, A, P> {
+    pub(crate) fn new(segvec: &'segvec SegVec<T, A, P>, chunk_size: usize) -> Self {
+        debug_assert_ne!(chunk_size, 0);
+        Self {
+            segvec,
+            pos: 0,
+            chunk_size,
+        }
+    }
+}
+
+impl<'segvec, T, A: Allocator, P: GrowthPolicy> Iterator for Chunks<'segvec, T, A, P> {
+    type Item = SegSlice<'segvec, T, A, P>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let remaining = self.segvec.len() - self.pos;
+        if remaining == 0 {
+            return None;
+        }
+
+        let len = cmp::min(remaining, self.chunk_size);
+        let chunk = SegSlice::new(self.segvec, self.pos, len);
+        self.pos += len;
+        Some(chunk)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.len();
+        (len, Some(len))
+    }
+}
+
+impl<T, A: Allocator, P: GrowthPolicy> ExactSizeIterator for Chunks<'_, T, A, P> {
+    fn len(&self) -> usize {
+        let remaining = self.segvec.len() - self.pos;
+        let (full, rem) = (remaining / self.chunk_size, remaining % self.chunk_size);
+        if rem > 0 {
+            full + 1
+        } else {
+            full
+        }
+    }
+}
@@ -0,0 +1,57 @@
+//! `serde` integration.
+//!
+//! A `SegVec` is serialized as a sequence, just like a `Vec`.
+use crate::{Allocator, GrowthPolicy, SegVec};
+use core::{cmp, fmt, marker::PhantomData, mem};
+use serde::{
+    de::{Deserialize, Deserializer, SeqAccess, Visitor},
+    ser::{Serialize, SerializeSeq, Serializer},
+};
+
+/// Visits a sequence, collecting its elements into a `SegVec`.
+struct SegVecVisitor<T>(PhantomData<fn() -> T>);
+
+/// The maximum number of bytes to pre-allocate based on a sequence's size
+/// hint. The size hint comes from the input, so it can't be trusted not to be
+/// enormous; if the sequence is actually longer than this, the `SegVec` will
+/// just grow as usual.
+const MAX_PREALLOC_BYTES: usize = 1024 * 1024;
+
+impl<T: Serialize, A: Allocator, P: GrowthPolicy> Serialize for SegVec<T, A, P> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.len()))?;
+        for elem in self {
+            seq.serialize_element(elem)?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for SegVec<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(SegVecVisitor(PhantomData))
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Visitor<'de> for SegVecVisitor<T> {
+    type Value = SegVec<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a sequence")
+    }
+
+    fn visit_seq<S: SeqAccess<'de>>(self, mut seq: S) -> Result<Self::Value, S::Error> {
+        // If we know how many elements there are, allocate a single data
+        // block that can hold all of them, rather than growing one block at a
+        // time.
+        let capacity = match seq.size_hint() {
+            Some(len) => cmp::min(len, MAX_PREALLOC_BYTES / cmp::max(mem::size_of::<T>(), 1)),
+            None => 0,
+        };
+        let mut segvec = SegVec::with_capacity(capacity);
+        while let Some(elem) = seq.next_element()? {
+            segvec.push(elem);
+        }
+        Ok(segvec)
+    }
+}
@@ -0,0 +1,376 @@
+//! A segmented vector which stores its first few elements inline.
+//!
+//! See [`SmallSegVec`] for details.
+use crate::SegVec;
+use core::{
+    cmp, fmt,
+    iter::{Chain, FromIterator},
+    mem::MaybeUninit,
+    ops::{Index, IndexMut},
+    ptr, slice,
+};
+
+/// A segmented vector which stores its first `INLINE` elements inside the
+/// struct, and the rest in a [`SegVec`].
+///
+/// A `SegVec` allocates an index block and a data block as soon as its first
+/// element is pushed. Programs which keep many vectors that usually only hold
+/// a few elements can avoid those allocations with a `SmallSegVec`: it only
+/// allocates once it holds more than `INLINE` elements. The elements after
+/// the first `INLINE` are stored in a `SegVec`.
+///
+/// The inline elements take the place of the small data blocks at the start
+/// of the layout, so the `SegVec` starts with a data block of `INLINE`
+/// elements (rounded up to a power of two), created with
+/// [`SegVec::with_first_block`], and grows as usual from there. This way, a
+/// `SmallSegVec` which spills doesn't allocate a run of tiny data blocks.
+///
+/// # Address stability
+///
+/// Unlike the elements in a `SegVec`, the first `INLINE` elements are stored
+/// inside the `SmallSegVec` itself, so **they move whenever the
+/// `SmallSegVec` is moved**. Only the elements at index `INLINE` and beyond
+/// keep their addresses when the `SmallSegVec` moves. Pushing never moves
+/// any elements, though.
+///
+/// # Examples
+///
+/// ```
+/// use segvec::SmallSegVec;
+///
+/// let mut small: SmallSegVec<i32, 4> = SmallSegVec::new();
+/// small.extend(0..4);
+/// assert!(!small.spilled());
+///
+/// small.push(4);
+/// assert!(small.spilled());
+/// assert_eq!(small[4], 4);
+/// assert!(small.iter().copied().eq(0..5));
+/// ```
+pub struct SmallSegVec<T, const INLINE: usize> {
+    /// The first `inline_len` elements.
+    inline: [MaybeUninit<T>; INLINE],
+
+    /// The number of elements in `inline`. If `heap` is not empty, this is
+    /// `INLINE`.
+    inline_len: usize,
+
+    /// The elements after the first `INLINE`.
+    heap: SegVec<T>,
+}
+
+/// An iterator over the elements of a [`SmallSegVec`].
+///
+/// This is returned by [`SmallSegVec::iter`].
+#[derive(Debug)]
+pub struct Iter<'segvec, T> {
+    inner: Chain<slice::Iter<'segvec, T>, crate::Iter<'segvec, T>>,
+}
+
+/// An iterator over mutable references to the elements of a
+/// [`SmallSegVec`].
+///
+/// This is returned by [`SmallSegVec::iter_mut`].
+#[derive(Debug)]
+pub struct IterMut<'segvec, T> {
+    inner: Chain<slice::IterMut<'segvec, T>, crate::IterMut<'segvec, T>>,
+}
+
+impl<T, const INLINE: usize> SmallSegVec<T, INLINE> {
+    /// Constructs a new, empty `SmallSegVec`.
+    ///
+    /// The `SmallSegVec` will not allocate until more than `INLINE` elements
+    /// are pushed onto it.
+    pub const fn new() -> Self {
+        Self {
+            // Safety: an array of `MaybeUninit`s doesn't need to be
+            // initialized.
+            inline: unsafe { MaybeUninit::<[MaybeUninit<T>; INLINE]>::uninit().assume_init() },
+            inline_len: 0,
+            heap: SegVec::new(),
+        }
+    }
+
+    /// Constructs a new, empty `SmallSegVec` with at least the specified
+    /// capacity.
+    ///
+    /// Nothing is allocated if `capacity` is at most `INLINE`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity exceeds `isize::MAX` bytes.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut this = Self::new();
+        this.reserve(capacity);
+        this
+    }
+
+    /// Returns the number of elements the `SmallSegVec` can hold without
+    /// allocating.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        INLINE + self.heap.capacity()
+    }
+
+    /// Returns the number of elements in the `SmallSegVec`.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.inline_len + self.heap.len()
+    }
+
+    /// Returns `true` if the `SmallSegVec` contains no elements.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if the `SmallSegVec` holds more than `INLINE` elements,
+    /// so that some of its elements are stored outside of the struct.
+    #[inline]
+    pub fn spilled(&self) -> bool {
+        !self.heap.is_empty()
+    }
+
+    /// Reserves capacity for at least `additional` more elements.
+    ///
+    /// Nothing is allocated if the elements fit inline. Otherwise, this
+    /// reserves capacity in the [`SegVec`] holding the rest of the elements;
+    /// see [`SegVec::reserve`] for details.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity exceeds `isize::MAX` bytes.
+    pub fn reserve(&mut self, additional: usize) {
+        let inline_free = INLINE - self.inline_len;
+        if additional > inline_free {
+            self.spill();
+            self.heap.reserve(additional - inline_free);
+        }
+    }
+
+    /// Returns a reference to the element at `idx`, or `None` if it is out of
+    /// bounds.
+    pub fn get(&self, idx: usize) -> Option<&T> {
+        if idx < INLINE {
+            return self.inline_slice().get(idx);
+        }
+
+        self.heap.get(idx - INLINE)
+    }
+
+    /// Returns a mutable reference to the element at `idx`, or `None` if it
+    /// is out of bounds.
+    pub fn get_mut(&mut self, idx: usize) -> Option<&mut T> {
+        if idx < INLINE {
+            return self.inline_slice_mut().get_mut(idx);
+        }
+
+        self.heap.get_mut(idx - INLINE)
+    }
+
+    /// Appends an element to the back of the `SmallSegVec`, returning its
+    /// index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the new capacity exceeds `isize::MAX` bytes.
+    pub fn push(&mut self, element: T) -> usize {
+        if self.inline_len < INLINE {
+            let idx = self.inline_len;
+            self.inline[idx].write(element);
+            self.inline_len += 1;
+            return idx;
+        }
+
+        self.spill();
+        INLINE + self.heap.push(element)
+    }
+
+    /// Allocates the first data block of the [`SegVec`] holding the elements
+    /// after the first `INLINE`, if it hasn't been allocated yet.
+    ///
+    /// The first data block holds `INLINE` elements, rounded up to a power of
+    /// two, so that the layout continues from the inline elements rather than
+    /// starting over with blocks of 1, 2, 2, 4... elements.
+    fn spill(&mut self) {
+        if self.heap.capacity() == 0 {
+            let first_block = cmp::min(
+                cmp::max(INLINE, 1).next_power_of_two(),
+                SegVec::<T>::MAX_FIRST_BLOCK_CAP,
+            );
+            self.heap = SegVec::with_first_block(first_block);
+        }
+    }
+
+    /// Returns an iterator over the elements in the `SmallSegVec`.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            inner: self.inline_slice().iter().chain(self.heap.iter()),
+        }
+    }
+
+    /// Returns an iterator over mutable references to the elements in the
+    /// `SmallSegVec`.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        let inline_len = self.inline_len;
+        // Safety: the first `inline_len` elements are initialized, and we
+        // have exclusive access to them.
+        let inline =
+            unsafe { slice::from_raw_parts_mut(self.inline.as_mut_ptr().cast::<T>(), inline_len) };
+        IterMut {
+            inner: inline.iter_mut().chain(self.heap.iter_mut()),
+        }
+    }
+
+    /// Returns the elements stored inline.
+    fn inline_slice(&self) -> &[T] {
+        // Safety: the first `inline_len` elements are initialized.
+        unsafe { slice::from_raw_parts(self.inline.as_ptr().cast(), self.inline_len) }
+    }
+
+    /// Returns the elements stored inline, mutably.
+    fn inline_slice_mut(&mut self) -> &mut [T] {
+        // Safety: the first `inline_len` elements are initialized, and we have
+        // exclusive access to them.
+        unsafe { slice::from_raw_parts_mut(self.inline.as_mut_ptr().cast(), self.inline_len) }
+    }
+}
+
+impl<T, const INLINE: usize> Index<usize> for SmallSegVec<T, INLINE> {
+    type Output = T;
+
+    #[track_caller]
+    fn index(&self, idx: usize) -> &Self::Output {
+        match self.get(idx) {
+            None => panic!(
+                "SmallSegVec index out of bounds: the len is {} but the index is {}",
+                self.len(),
+                idx
+            ),
+            Some(elem) => elem,
+        }
+    }
+}
+
+impl<T, const INLINE: usize> IndexMut<usize> for SmallSegVec<T, INLINE> {
+    #[track_caller]
+    fn index_mut(&mut self, idx: usize) -> &mut Self::Output {
+        let len = self.len();
+        match self.get_mut(idx) {
+            None => panic!(
+                "SmallSegVec index out of bounds: the len is {} but the index is {}",
+                len, idx
+            ),
+            Some(elem) => elem,
+        }
+    }
+}
+
+impl<T, const INLINE: usize> Extend<T> for SmallSegVec<T, INLINE> {
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let iter = iter.into_iter();
+        self.reserve(crate::size_hint_capacity(&iter));
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+impl<T, const INLINE: usize> FromIterator<T> for SmallSegVec<T, INLINE> {
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let mut this = Self::new();
+        this.extend(iter);
+        this
+    }
+}
+
+impl<'segvec, T, const INLINE: usize> IntoIterator for &'segvec SmallSegVec<T, INLINE> {
+    type IntoIter = Iter<'segvec, T>;
+    type Item = &'segvec T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'segvec, T, const INLINE: usize> IntoIterator for &'segvec mut SmallSegVec<T, INLINE> {
+    type IntoIter = IterMut<'segvec, T>;
+    type Item = &'segvec mut T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<T: Clone, const INLINE: usize> Clone for SmallSegVec<T, INLINE> {
+    fn clone(&self) -> Self {
+        let mut clone = Self::with_capacity(self.len());
+        clone.extend(self.iter().cloned());
+        clone
+    }
+}
+
+impl<T: fmt::Debug, const INLINE: usize> fmt::Debug for SmallSegVec<T, INLINE> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T, const INLINE: usize> Default for SmallSegVec<T, INLINE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const INLINE: usize> Drop for SmallSegVec<T, INLINE> {
+    fn drop(&mut self) {
+        // The `SegVec` drops its own elements, but the inline elements are
+        // `MaybeUninit`s.
+        let inline = self.inline_slice_mut() as *mut [T];
+        // Safety: the inline elements are initialized, and are never used
+        // again.
+        unsafe { ptr::drop_in_place(inline) };
+    }
+}
+
+// === impl Iter ===
+
+impl<'segvec, T> Iterator for Iter<'segvec, T> {
+    type Item = &'segvec T;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+// === impl IterMut ===
+
+impl<'segvec, T> Iterator for IterMut<'segvec, T> {
+    type Item = &'segvec mut T;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
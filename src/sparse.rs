@@ -0,0 +1,326 @@
+//! A sparse segmented vector, which only allocates the data blocks containing
+//! its elements.
+//!
+//! See [`SparseSegVec`] for details.
+use crate::location::Location;
+use alloc::{boxed::Box, vec::Vec};
+use core::{fmt, iter::FromIterator, slice};
+
+/// A sparse segmented vector, which only allocates the data blocks containing
+/// its elements.
+///
+/// A `SparseSegVec<T>` maps indices to elements, like a `SegVec<Option<T>>`,
+/// but a data block is only allocated once an element is inserted into it,
+/// and is freed once its last element is removed. Data blocks which haven't
+/// been allocated are holes in the index, and iteration skips over them
+/// entirely. This makes a `SparseSegVec` a good fit for indices which are
+/// dense in some places and sparse in others, such as IDs which are
+/// clustered.
+///
+/// As in a [`SegVec`](crate::SegVec), elements never move once they're
+/// inserted.
+///
+/// # Examples
+///
+/// ```
+/// use segvec::SparseSegVec;
+///
+/// let mut segvec = SparseSegVec::new();
+/// segvec.insert(3, "three");
+/// segvec.insert(1_000_000, "a million");
+///
+/// assert_eq!(segvec.get(3), Some(&"three"));
+/// assert_eq!(segvec.get(4), None);
+/// assert_eq!(segvec.len(), 2);
+///
+/// let elements: Vec<_> = segvec.iter().collect();
+/// assert_eq!(elements, [(3, &"three"), (1_000_000, &"a million")]);
+/// ```
+pub struct SparseSegVec<T> {
+    /// The number of elements in the `SparseSegVec`.
+    len: usize,
+    /// The "index block". A data block is `None` if it contains no elements.
+    index: Vec<Option<SparseBlock<T>>>,
+}
+
+/// An iterator over the elements of a [`SparseSegVec`], along with their
+/// indices.
+///
+/// This is returned by [`SparseSegVec::iter`].
+#[derive(Debug)]
+pub struct Iter<'segvec, T> {
+    len: usize,
+    blocks: slice::Iter<'segvec, Option<SparseBlock<T>>>,
+    curr_block: (usize, slice::Iter<'segvec, Option<T>>),
+}
+
+/// An iterator over mutable references to the elements of a
+/// [`SparseSegVec`], along with their indices.
+///
+/// This is returned by [`SparseSegVec::iter_mut`].
+#[derive(Debug)]
+pub struct IterMut<'segvec, T> {
+    len: usize,
+    blocks: slice::IterMut<'segvec, Option<SparseBlock<T>>>,
+    curr_block: (usize, slice::IterMut<'segvec, Option<T>>),
+}
+
+#[derive(Debug)]
+struct SparseBlock<T> {
+    /// The index of the first slot in this block.
+    start: usize,
+    /// The number of slots in this block which contain an element.
+    len: usize,
+    slots: Box<[Option<T>]>,
+}
+
+impl<T> SparseSegVec<T> {
+    /// Constructs a new, empty `SparseSegVec`.
+    ///
+    /// The `SparseSegVec` will not allocate until elements are inserted into
+    /// it.
+    pub const fn new() -> Self {
+        Self {
+            len: 0,
+            index: Vec::new(),
+        }
+    }
+
+    /// Returns the number of elements in the `SparseSegVec`.
+    ///
+    /// This is the number of indices which contain an element, not the
+    /// highest index.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the `SparseSegVec` contains no elements.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns a reference to the element at `idx`, or `None` if there is no
+    /// element at `idx`.
+    pub fn get(&self, idx: usize) -> Option<&T> {
+        let loc = Location::of(idx);
+        let block = self.index.get(loc.data_block())?.as_ref()?;
+        block.slots[loc.slot].as_ref()
+    }
+
+    /// Returns a mutable reference to the element at `idx`, or `None` if
+    /// there is no element at `idx`.
+    pub fn get_mut(&mut self, idx: usize) -> Option<&mut T> {
+        let loc = Location::of(idx);
+        let block = self.index.get_mut(loc.data_block())?.as_mut()?;
+        block.slots[loc.slot].as_mut()
+    }
+
+    /// Returns `true` if there is an element at `idx`.
+    pub fn contains(&self, idx: usize) -> bool {
+        self.get(idx).is_some()
+    }
+
+    /// Inserts an element at `idx`, allocating the data block containing
+    /// `idx` if necessary.
+    ///
+    /// If there was already an element at `idx`, it is replaced, and the old
+    /// element is returned.
+    ///
+    /// # Panics
+    ///
+    /// If `idx` is `usize::MAX`.
+    pub fn insert(&mut self, idx: usize, value: T) -> Option<T> {
+        let loc = Location::of(idx);
+        let data_block = loc.data_block();
+        if data_block >= self.index.len() {
+            self.index.resize_with(data_block + 1, || None);
+        }
+
+        let block = self.index[data_block].get_or_insert_with(|| SparseBlock::new(loc));
+        let old = block.slots[loc.slot].replace(value);
+        if old.is_none() {
+            block.len += 1;
+            self.len += 1;
+        }
+        old
+    }
+
+    /// Removes the element at `idx`, returning it, or `None` if there was no
+    /// element at `idx`.
+    ///
+    /// If this was the last element in its data block, the data block is
+    /// freed.
+    pub fn remove(&mut self, idx: usize) -> Option<T> {
+        let loc = Location::of(idx);
+        let slot = self.index.get_mut(loc.data_block())?;
+        let block = slot.as_mut()?;
+        let elem = block.slots[loc.slot].take()?;
+        block.len -= 1;
+        self.len -= 1;
+
+        if block.len == 0 {
+            *slot = None;
+            // Don't keep holes at the end of the index.
+            while let Some(None) = self.index.last() {
+                self.index.pop();
+            }
+        }
+        Some(elem)
+    }
+
+    /// Removes every element, freeing all of the data blocks.
+    pub fn clear(&mut self) {
+        self.index.clear();
+        self.len = 0;
+    }
+
+    /// Returns an iterator over the elements, along with their indices, in
+    /// index order.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            len: self.len,
+            blocks: self.index.iter(),
+            curr_block: (0, [].iter()),
+        }
+    }
+
+    /// Returns an iterator over mutable references to the elements, along
+    /// with their indices, in index order.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            len: self.len,
+            blocks: self.index.iter_mut(),
+            curr_block: (0, [].iter_mut()),
+        }
+    }
+}
+
+impl<T> Default for SparseSegVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SparseSegVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<T> Extend<(usize, T)> for SparseSegVec<T> {
+    fn extend<I: IntoIterator<Item = (usize, T)>>(&mut self, iter: I) {
+        for (idx, value) in iter {
+            self.insert(idx, value);
+        }
+    }
+}
+
+impl<T> FromIterator<(usize, T)> for SparseSegVec<T> {
+    fn from_iter<I: IntoIterator<Item = (usize, T)>>(iter: I) -> Self {
+        let mut this = Self::new();
+        this.extend(iter);
+        this
+    }
+}
+
+impl<'segvec, T> IntoIterator for &'segvec SparseSegVec<T> {
+    type IntoIter = Iter<'segvec, T>;
+    type Item = (usize, &'segvec T);
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'segvec, T> IntoIterator for &'segvec mut SparseSegVec<T> {
+    type IntoIter = IterMut<'segvec, T>;
+    type Item = (usize, &'segvec mut T);
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+// === impl Iter ===
+
+impl<'segvec, T> Iterator for Iter<'segvec, T> {
+    type Item = (usize, &'segvec T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+
+        loop {
+            let (idx, slots) = &mut self.curr_block;
+            for slot in slots {
+                *idx += 1;
+                if let Some(elem) = slot {
+                    self.len -= 1;
+                    return Some((*idx - 1, elem));
+                }
+            }
+
+            // Skip over any holes.
+            let block = self.blocks.by_ref().flatten().next()?;
+            self.curr_block = (block.start, block.slots.iter());
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+// === impl IterMut ===
+
+impl<'segvec, T> Iterator for IterMut<'segvec, T> {
+    type Item = (usize, &'segvec mut T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+
+        loop {
+            let (idx, slots) = &mut self.curr_block;
+            for slot in slots {
+                *idx += 1;
+                if let Some(elem) = slot {
+                    self.len -= 1;
+                    return Some((*idx - 1, elem));
+                }
+            }
+
+            // Skip over any holes.
+            let block = self.blocks.by_ref().flatten().next()?;
+            self.curr_block = (block.start, block.slots.iter_mut());
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+// === impl SparseBlock ===
+
+impl<T> SparseBlock<T> {
+    /// Returns a new, empty block containing the slot at `loc`.
+    fn new(loc: Location) -> Self {
+        let len = Location::block_len(loc.superblock);
+        let mut slots = Vec::with_capacity(len);
+        slots.resize_with(len, || None);
+        Self {
+            start: Location::start_of(loc.superblock, loc.block),
+            len: 0,
+            slots: slots.into_boxed_slice(),
+        }
+    }
+}
@@ -0,0 +1,396 @@
+//! A segmented vector whose index block never moves.
+//!
+//! See [`StableSegVec`] for details.
+use crate::{
+    fixed_index::{self, FixedIndex},
+    location::Location,
+};
+use core::{
+    cell::UnsafeCell,
+    fmt,
+    iter::FromIterator,
+    mem::MaybeUninit,
+    ops::{Index, IndexMut},
+    ptr,
+};
+
+/// A segmented vector whose index block never moves.
+///
+/// A [`SegVec`](crate::SegVec) stores pointers to its data blocks in an index
+/// block, which is reallocated, and copied, as data blocks are added. That's a
+/// hidden _O(sqrt(n))_ copy every so often while the `SegVec` grows.
+///
+/// A `StableSegVec` instead has a fixed-size index, with one entry per
+/// superblock, each of which points to an array of pointers to that
+/// superblock's data blocks. Since there can only be `usize::BITS`
+/// superblocks, the index is stored inline, and the per-superblock arrays are
+/// allocated once, at their final size, the first time a data block in that
+/// superblock is needed. Growing a `StableSegVec` never copies anything, so
+/// every push takes constant time, aside from allocating, and neither the
+/// elements nor the pointers to the data blocks ever move.
+///
+/// The tradeoff is size: the inline index makes a `StableSegVec` `usize::BITS`
+/// pointers larger than a `SegVec`. Data blocks are always laid out as in
+/// Brodnik et al.'s paper, starting with a data block of one element.
+///
+/// # Examples
+///
+/// ```
+/// use segvec::StableSegVec;
+///
+/// let mut segvec = StableSegVec::new();
+/// for i in 0..100 {
+///     segvec.push(i);
+/// }
+///
+/// assert_eq!(segvec.len(), 100);
+/// assert_eq!(segvec[42], 42);
+/// assert_eq!(segvec.pop(), Some(99));
+/// assert!(segvec.iter().copied().eq(0..99));
+/// ```
+pub struct StableSegVec<T> {
+    /// The number of elements in the `StableSegVec`.
+    len: usize,
+    /// The number of slots in the data blocks which have been allocated.
+    capacity: usize,
+    index: FixedIndex<Slot<T>>,
+}
+
+/// An iterator over the elements of a [`StableSegVec`].
+///
+/// This is returned by [`StableSegVec::iter`].
+pub struct Iter<'segvec, T> {
+    slots: fixed_index::Iter<'segvec, Slot<T>>,
+    len: usize,
+}
+
+/// An iterator over mutable references to the elements of a
+/// [`StableSegVec`].
+///
+/// This is returned by [`StableSegVec::iter_mut`].
+pub struct IterMut<'segvec, T> {
+    slots: fixed_index::Iter<'segvec, Slot<T>>,
+    len: usize,
+}
+
+struct Slot<T>(UnsafeCell<MaybeUninit<T>>);
+
+// Safety: the elements are only mutated through a mutable reference to the
+// `StableSegVec`, so sharing it only shares references to the elements.
+unsafe impl<T: Sync> Sync for StableSegVec<T> {}
+
+impl<T> StableSegVec<T> {
+    /// Constructs a new, empty `StableSegVec`.
+    ///
+    /// The `StableSegVec` will not allocate until elements are pushed onto it.
+    pub const fn new() -> Self {
+        Self {
+            len: 0,
+            capacity: 0,
+            index: FixedIndex::new(),
+        }
+    }
+
+    /// Constructs a new, empty `StableSegVec` with at least the specified
+    /// capacity.
+    ///
+    /// # Panics
+    ///
+    /// If allocating a data block fails.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut this = Self::new();
+        this.reserve(capacity);
+        this
+    }
+
+    /// Returns the number of elements the `StableSegVec` can hold without
+    /// allocating.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of elements in the `StableSegVec`.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the `StableSegVec` contains no elements.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Reserves capacity for at least `additional` more elements.
+    ///
+    /// Data blocks are added, in order, until there is enough capacity.
+    /// Nothing that has already been allocated is moved.
+    ///
+    /// # Panics
+    ///
+    /// If the new capacity overflows a `usize`, or if allocating a data block
+    /// fails.
+    pub fn reserve(&mut self, additional: usize) {
+        let required = self
+            .len
+            .checked_add(additional)
+            .expect("StableSegVec capacity overflow");
+        while self.capacity < required {
+            // Allocate the data block starting at the first slot we don't
+            // have yet.
+            let loc = Location::of(self.capacity);
+            self.index.get_or_alloc(self.capacity);
+            self.capacity += Location::block_len(loc.superblock);
+        }
+    }
+
+    /// Returns a reference to the element at `idx`, or `None` if it is out of
+    /// bounds.
+    pub fn get(&self, idx: usize) -> Option<&T> {
+        if idx >= self.len {
+            return None;
+        }
+
+        let slot = self.index.get(idx)?;
+        // Safety: every slot before `len` is initialized.
+        Some(unsafe { (*slot.0.get()).assume_init_ref() })
+    }
+
+    /// Returns a mutable reference to the element at `idx`, or `None` if it
+    /// is out of bounds.
+    pub fn get_mut(&mut self, idx: usize) -> Option<&mut T> {
+        if idx >= self.len {
+            return None;
+        }
+
+        let slot = self.index.get_mut(idx)?;
+        // Safety: every slot before `len` is initialized.
+        Some(unsafe { slot.0.get_mut().assume_init_mut() })
+    }
+
+    /// Appends an element to the back of the `StableSegVec`, returning its
+    /// index.
+    ///
+    /// # Panics
+    ///
+    /// If the new capacity overflows a `usize`, or if allocating a data block
+    /// fails.
+    pub fn push(&mut self, element: T) -> usize {
+        self.reserve(1);
+        let idx = self.len;
+        let slot = self
+            .index
+            .get_mut(idx)
+            .expect("reserved slots must be allocated");
+        slot.0.get_mut().write(element);
+        self.len += 1;
+        idx
+    }
+
+    /// Removes the last element from the `StableSegVec` and returns it, or
+    /// `None` if it is empty.
+    ///
+    /// Data blocks are not deallocated.
+    pub fn pop(&mut self) -> Option<T> {
+        let idx = self.len.checked_sub(1)?;
+        self.len = idx;
+        let slot = self.index.get_mut(idx)?;
+        // Safety: the slot was initialized, and it's no longer owned by the
+        // `StableSegVec`.
+        Some(unsafe { slot.0.get_mut().assume_init_read() })
+    }
+
+    /// Removes every element from the `StableSegVec`.
+    ///
+    /// Data blocks are not deallocated.
+    pub fn clear(&mut self) {
+        let len = self.len;
+        // If dropping an element panics, the rest are leaked, rather than
+        // dropped twice.
+        self.len = 0;
+        for (_, slot) in self.index.iter().take(len) {
+            // Safety: the slot was initialized, and it's no longer owned by
+            // the `StableSegVec`.
+            unsafe { ptr::drop_in_place((*slot.0.get()).as_mut_ptr()) };
+        }
+    }
+
+    /// Returns an iterator over the elements in the `StableSegVec`.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            slots: self.index.iter(),
+            len: self.len,
+        }
+    }
+
+    /// Returns an iterator over mutable references to the elements in the
+    /// `StableSegVec`.
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            slots: self.index.iter(),
+            len: self.len,
+        }
+    }
+}
+
+impl<T> Index<usize> for StableSegVec<T> {
+    type Output = T;
+
+    #[track_caller]
+    fn index(&self, idx: usize) -> &Self::Output {
+        match self.get(idx) {
+            None => panic!(
+                "StableSegVec index out of bounds: the len is {} but the index is {}",
+                self.len(),
+                idx
+            ),
+            Some(elem) => elem,
+        }
+    }
+}
+
+impl<T> IndexMut<usize> for StableSegVec<T> {
+    #[track_caller]
+    fn index_mut(&mut self, idx: usize) -> &mut Self::Output {
+        let len = self.len();
+        match self.get_mut(idx) {
+            None => panic!(
+                "StableSegVec index out of bounds: the len is {} but the index is {}",
+                len, idx
+            ),
+            Some(elem) => elem,
+        }
+    }
+}
+
+impl<T> Extend<T> for StableSegVec<T> {
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let iter = iter.into_iter();
+        self.reserve(crate::size_hint_capacity(&iter));
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for StableSegVec<T> {
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let mut this = Self::new();
+        this.extend(iter);
+        this
+    }
+}
+
+impl<'segvec, T> IntoIterator for &'segvec StableSegVec<T> {
+    type IntoIter = Iter<'segvec, T>;
+    type Item = &'segvec T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'segvec, T> IntoIterator for &'segvec mut StableSegVec<T> {
+    type IntoIter = IterMut<'segvec, T>;
+    type Item = &'segvec mut T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for StableSegVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T> Default for StableSegVec<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Drop for StableSegVec<T> {
+    fn drop(&mut self) {
+        // The index frees the data blocks, but it doesn't know which slots
+        // are initialized.
+        self.clear();
+    }
+}
+
+// === impl Iter ===
+
+impl<'segvec, T> Iterator for Iter<'segvec, T> {
+    type Item = &'segvec T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let (_, slot) = self.slots.next()?;
+        self.len -= 1;
+        // Safety: every slot before the `StableSegVec`'s length is
+        // initialized.
+        Some(unsafe { (*slot.0.get()).assume_init_ref() })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> fmt::Debug for Iter<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Iter").field("len", &self.len).finish()
+    }
+}
+
+// === impl IterMut ===
+
+impl<'segvec, T> Iterator for IterMut<'segvec, T> {
+    type Item = &'segvec mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let (_, slot) = self.slots.next()?;
+        self.len -= 1;
+        // Safety: every slot before the `StableSegVec`'s length is
+        // initialized, and the iterator borrows the `StableSegVec` mutably,
+        // visiting each slot once.
+        Some(unsafe { (*slot.0.get()).assume_init_mut() })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+impl<T> fmt::Debug for IterMut<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IterMut").field("len", &self.len).finish()
+    }
+}
+
+// === impl Slot ===
+
+impl<T> Default for Slot<T> {
+    fn default() -> Self {
+        Self(UnsafeCell::new(MaybeUninit::uninit()))
+    }
+}
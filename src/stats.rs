@@ -0,0 +1,105 @@
+//! Memory usage statistics for a `SegVec`.
+//!
+//! See [`SegVec::stats`] for details.
+use crate::{Allocator, GrowthPolicy, SegVec};
+use core::{mem, ptr::NonNull};
+
+/// A snapshot of a [`SegVec`]'s memory usage and data block layout.
+///
+/// This is returned by [`SegVec::stats`].
+///
+/// A `SegVec` of zero-sized elements never allocates, so it has no data
+/// blocks, and all of its byte counts are zero.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct SegVecStats {
+    /// The number of elements in the `SegVec`.
+    pub len: usize,
+
+    /// The number of elements the `SegVec` can hold without allocating.
+    pub capacity: usize,
+
+    /// The number of allocated data blocks.
+    ///
+    /// This doesn't include the low-size data blocks which are skipped when a
+    /// `SegVec` is created with a large first data block.
+    pub data_blocks: usize,
+
+    /// The superblock containing the last allocated data block.
+    ///
+    /// This is `None` if no data blocks are allocated, or if the growth policy
+    /// doesn't group its data blocks into superblocks. Only the default
+    /// [`Brodnik`](crate::Brodnik) policy has superblocks.
+    pub superblock: Option<usize>,
+
+    /// The number of allocated element slots which don't hold an element.
+    ///
+    /// With the default growth policy, this is _O(sqrt(n))_.
+    pub wasted_slots: usize,
+
+    /// The number of bytes allocated for data blocks.
+    pub data_bytes: usize,
+
+    /// The number of bytes allocated for the index block.
+    pub index_bytes: usize,
+
+    /// The capacity of the largest allocated data block, or `None` if no data
+    /// blocks are allocated.
+    pub largest_block: Option<usize>,
+
+    /// The capacity of the smallest allocated data block, or `None` if no
+    /// data blocks are allocated.
+    pub smallest_block: Option<usize>,
+}
+
+impl<T, A: Allocator, P: GrowthPolicy> SegVec<T, A, P> {
+    /// Returns statistics describing the `SegVec`'s memory usage and data
+    /// block layout.
+    ///
+    /// This walks the index, so it takes time proportional to the number of
+    /// data blocks.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use segvec::SegVec;
+    ///
+    /// let mut sv: SegVec<u32> = SegVec::with_capacity(16);
+    /// sv.extend(0..20);
+    ///
+    /// let stats = sv.stats();
+    /// assert_eq!(stats.len, 20);
+    /// assert_eq!(stats.capacity, 32);
+    /// assert_eq!(stats.data_blocks, 2);
+    /// assert_eq!(stats.wasted_slots, 12);
+    /// assert_eq!(stats.data_bytes, 32 * 4);
+    /// assert_eq!(stats.largest_block, Some(16));
+    /// assert_eq!(stats.smallest_block, Some(16));
+    /// ```
+    pub fn stats(&self) -> SegVecStats {
+        let data_blocks = self.index.len();
+        let capacities = (0..data_blocks).map(|block| self.block_capacity(block));
+        let (wasted_slots, data_bytes) = if Self::IS_ZST {
+            (0, 0)
+        } else {
+            (
+                self.capacity - self.len(),
+                self.capacity * mem::size_of::<T>(),
+            )
+        };
+
+        SegVecStats {
+            len: self.len(),
+            capacity: self.capacity(),
+            data_blocks,
+            superblock: data_blocks
+                .checked_sub(1)
+                .and_then(|last| P::superblock(last + self.meta.skipped_blocks)),
+            wasted_slots,
+            data_bytes,
+            index_bytes: self.index.capacity() * mem::size_of::<NonNull<T>>(),
+            largest_block: capacities.clone().max(),
+            smallest_block: capacities.min(),
+        }
+    }
+}
@@ -157,3 +157,1723 @@ proptest! {
         );
     }
 }
+
+proptest! {
+    #[test]
+    fn binary_search(mut vec: Vec<usize>, capacity in 0usize..1024, needle: usize) {
+        vec.sort_unstable();
+        vec.dedup();
+
+        let mut segvec = SegVec::with_capacity(capacity);
+        segvec.extend(vec.iter().copied());
+
+        for (i, elem) in vec.iter().enumerate() {
+            prop_assert_eq!(segvec.binary_search(elem), Ok(i), "segvec={:#?}", segvec.debug_details());
+        }
+        prop_assert_eq!(segvec.binary_search(&needle), vec.binary_search(&needle));
+    }
+
+    #[test]
+    fn partition_point(vec: Vec<usize>, pivot: usize) {
+        let mut vec = vec;
+        vec.sort_unstable();
+        let segvec: SegVec<usize> = vec.iter().copied().collect();
+
+        prop_assert_eq!(
+            segvec.partition_point(|&x| x < pivot),
+            vec.partition_point(|&x| x < pivot),
+        );
+    }
+
+    #[test]
+    fn select_nth_unstable(vec: Vec<u8>, index: usize) {
+        if vec.is_empty() {
+            return Ok(());
+        }
+        let index = index % vec.len();
+
+        let mut segvec: SegVec<u8> = vec.iter().copied().collect();
+        let nth = *segvec.select_nth_unstable(index);
+
+        let mut sorted = vec.clone();
+        sorted.sort_unstable();
+        prop_assert_eq!(nth, sorted[index]);
+        for i in 0..index {
+            prop_assert!(segvec[i] <= nth, "segvec[{}] = {}; nth={}", i, segvec[i], nth);
+        }
+        for i in index..segvec.len() {
+            prop_assert!(segvec[i] >= nth, "segvec[{}] = {}; nth={}", i, segvec[i], nth);
+        }
+    }
+}
+
+/// Runs McIlroy's quicksort adversary against `select_nth_unstable_by`: it
+/// settles the order of the elements lazily, as they're compared, so that
+/// every median-of-three pivot is as bad as possible.
+#[test]
+fn select_nth_unstable_adversary() {
+    const LEN: usize = 4096;
+    const GAS: usize = usize::MAX;
+
+    let mut vals = vec![GAS; LEN];
+    let mut solid = 0;
+    let mut candidate = 0;
+    let mut comparisons = 0;
+    let mut segvec: SegVec<usize> = (0..LEN).collect();
+    let nth = *segvec.select_nth_unstable_by(LEN / 2, |&a, &b| {
+        comparisons += 1;
+        if vals[a] == GAS && vals[b] == GAS {
+            let frozen = if a == candidate { a } else { b };
+            vals[frozen] = solid;
+            solid += 1;
+        }
+        if vals[a] == GAS {
+            candidate = a;
+        } else if vals[b] == GAS {
+            candidate = b;
+        }
+        vals[a].cmp(&vals[b])
+    });
+
+    assert!(comparisons < 64 * LEN, "comparisons={}", comparisons);
+    assert!(segvec.iter().take(LEN / 2).all(|&x| vals[x] <= vals[nth]));
+    assert!(segvec.iter().skip(LEN / 2).all(|&x| vals[x] >= vals[nth]));
+}
+
+proptest! {
+    #[test]
+    fn reverse(mut vec: Vec<usize>, capacity in 0usize..1024) {
+        let mut segvec = SegVec::with_capacity(capacity);
+        segvec.extend(vec.iter().copied());
+
+        vec.reverse();
+        segvec.reverse();
+        prop_assert!(segvec.iter().eq(vec.iter()), "vec={:?}; segvec={:#?}", vec, segvec.debug_details());
+    }
+
+    #[test]
+    fn rotate(mut vec: Vec<usize>, mid: usize) {
+        let mut segvec: SegVec<usize> = vec.iter().copied().collect();
+        let mid = mid % (vec.len() + 1);
+
+        vec.rotate_left(mid);
+        segvec.rotate_left(mid);
+        prop_assert!(segvec.iter().eq(vec.iter()), "mid={}; vec={:?}; segvec={:?}", mid, vec, segvec);
+
+        vec.rotate_right(mid);
+        segvec.rotate_right(mid);
+        prop_assert!(segvec.iter().eq(vec.iter()), "mid={}; vec={:?}; segvec={:?}", mid, vec, segvec);
+    }
+
+    #[test]
+    fn fill(vec: Vec<usize>, value: usize) {
+        let mut segvec: SegVec<usize> = vec.iter().copied().collect();
+        segvec.fill(value);
+        prop_assert_eq!(segvec.len(), vec.len());
+        prop_assert!(segvec.iter().all(|&x| x == value), "segvec={:?}", segvec);
+
+        let mut n = 0;
+        segvec.fill_with(|| { n += 1; n });
+        prop_assert!(segvec.iter().copied().eq(1..=vec.len()), "segvec={:?}", segvec);
+    }
+}
+
+proptest! {
+    #[test]
+    fn slice(vec: Vec<usize>, capacity in 0usize..1024, a: usize, b: usize) {
+        let mut segvec = SegVec::with_capacity(capacity);
+        segvec.extend(vec.iter().copied());
+
+        let a = a % (vec.len() + 1);
+        let b = b % (vec.len() + 1);
+        let (start, end) = if a <= b { (a, b) } else { (b, a) };
+
+        let slice = segvec.slice(start..end);
+        prop_assert_eq!(slice.len(), end - start);
+        prop_assert!(slice.iter().eq(vec[start..end].iter()), "slice={:?}; segvec={:#?}", slice, segvec.debug_details());
+        for (i, elem) in vec[start..end].iter().enumerate() {
+            prop_assert_eq!(&slice[i], elem);
+        }
+        prop_assert_eq!(slice.get(end - start), None);
+        if let Some(contiguous) = slice.as_slice() {
+            prop_assert_eq!(contiguous, &vec[start..end]);
+        }
+    }
+
+    #[test]
+    fn windows(vec: Vec<usize>, size in 1usize..32) {
+        let segvec: SegVec<usize> = vec.iter().copied().collect();
+        let windows = segvec.windows(size);
+        prop_assert_eq!(windows.len(), vec.windows(size).len());
+        for (window, expected) in windows.zip(vec.windows(size)) {
+            prop_assert!(window.iter().eq(expected.iter()), "window={:?}; expected={:?}", window, expected);
+        }
+    }
+
+    #[test]
+    fn chunks(vec: Vec<usize>, size in 1usize..32) {
+        let segvec: SegVec<usize> = vec.iter().copied().collect();
+        let chunks = segvec.chunks(size);
+        prop_assert_eq!(chunks.len(), vec.chunks(size).len());
+        for (chunk, expected) in chunks.zip(vec.chunks(size)) {
+            prop_assert!(chunk.iter().eq(expected.iter()), "chunk={:?}; expected={:?}", chunk, expected);
+        }
+    }
+}
+
+#[test]
+fn try_reserve_capacity_overflow() {
+    let mut segvec: SegVec<u64> = (0..10).collect();
+    let capacity = segvec.capacity();
+
+    // Overflows `usize` when added to the length.
+    let error = segvec.try_reserve(usize::MAX).unwrap_err();
+    assert_eq!(error.kind(), TryReserveErrorKind::CapacityOverflow);
+
+    // Fits in `usize` elements, but not in `isize::MAX` bytes.
+    let error = segvec
+        .try_reserve(isize::MAX as usize / mem::size_of::<u64>())
+        .unwrap_err();
+    assert_eq!(error.kind(), TryReserveErrorKind::CapacityOverflow);
+
+    assert_eq!(segvec.capacity(), capacity);
+    assert!(segvec.iter().copied().eq(0..10));
+}
+
+#[test]
+fn try_reserve_alloc_error() {
+    // An element type that's large enough that the first data block can't
+    // possibly be allocated, even though the total capacity is less than
+    // `isize::MAX` bytes.
+    type Huge = [u64; 1 << 20];
+    const CAPACITY: usize = isize::MAX as usize / (mem::size_of::<Huge>() * 2);
+
+    let error = SegVec::<Huge>::try_with_capacity(CAPACITY).unwrap_err();
+    assert!(
+        matches!(error.kind(), TryReserveErrorKind::AllocError { .. }),
+        "error={:?}",
+        error
+    );
+
+    let mut segvec: SegVec<Huge> = SegVec::new();
+    assert!(segvec.try_reserve(CAPACITY).is_err());
+    assert_eq!(segvec.capacity(), 0);
+    assert!(segvec.is_empty());
+}
+
+proptest! {
+    #[test]
+    fn reserve_exact(cap in 1..A_REASONABLE_CAPACITY, additional in 0..A_REASONABLE_CAPACITY) {
+        let mut segvec: SegVec<u8> = SegVec::new();
+        segvec.reserve_exact(cap);
+        prop_assert_eq!(
+            segvec.capacity(),
+            cap.next_power_of_two(),
+            "segvec={:#?}",
+            segvec.debug_details(),
+        );
+
+        // Growing a non-empty `SegVec` adds data blocks one at a time, so it
+        // shouldn't overshoot by more than the size of the last block.
+        segvec.extend(std::iter::repeat(1).take(cap));
+        segvec.reserve_exact(additional);
+        let required = cap + additional;
+        prop_assert!(segvec.capacity() >= required);
+        let last_block_cap = segvec.block_capacity(segvec.index.len() - 1);
+        prop_assert!(
+            segvec.capacity() - required < last_block_cap,
+            "required={}; segvec={:#?}",
+            required,
+            segvec.debug_details(),
+        );
+    }
+
+    #[test]
+    fn with_first_block(log2 in 0u32..16, vec: Vec<usize>) {
+        let cap = 1 << log2;
+        let mut segvec = SegVec::with_first_block(cap);
+        prop_assert_eq!(segvec.capacity(), cap);
+        prop_assert_eq!(segvec.index.len(), 1);
+
+        for (i, elem) in vec.iter().enumerate() {
+            prop_assert_eq!(segvec.push(*elem), i);
+        }
+        prop_assert!(segvec.iter().eq(vec.iter()), "segvec={:#?}", segvec.debug_details());
+    }
+
+    #[test]
+    fn addresses_are_stable(cap in 0..A_REASONABLE_CAPACITY, vec: Vec<u8>) {
+        let mut segvec = SegVec::with_capacity(cap);
+        let mut addresses = Vec::new();
+        for elem in &vec {
+            let idx = segvec.push(*elem);
+            addresses.push(&segvec[idx] as *const u8);
+        }
+
+        // Every data block before the one containing the last element is
+        // full, and the rest are empty.
+        let lens: Vec<usize> = (0..segvec.index.len()).map(|block| segvec.block_len(block)).collect();
+        prop_assert_eq!(lens.iter().sum::<usize>(), vec.len());
+        for (block, &len) in lens.iter().enumerate() {
+            let cap = segvec.block_capacity(block);
+            prop_assert!(len == cap || lens[block + 1..].iter().all(|&len| len == 0));
+        }
+
+        for (i, address) in addresses.into_iter().enumerate() {
+            prop_assert_eq!(&segvec[i] as *const u8, address);
+        }
+    }
+}
+
+#[test]
+fn drops_elements() {
+    use std::rc::Rc;
+
+    let rc = Rc::new(());
+    let mut segvec = SegVec::new();
+    for _ in 0..100 {
+        segvec.push(rc.clone());
+    }
+    assert_eq!(Rc::strong_count(&rc), 101);
+
+    drop(segvec);
+    assert_eq!(Rc::strong_count(&rc), 1);
+}
+
+#[test]
+fn zero_sized_elements() {
+    #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+    struct Marker;
+
+    let mut segvec = SegVec::new();
+    assert_eq!(segvec.capacity(), usize::MAX);
+    for i in 0..100 {
+        assert_eq!(segvec.push(Marker), i);
+    }
+    assert_eq!(segvec.len(), 100);
+    assert_eq!(segvec.index.len(), 0);
+    assert_eq!(segvec[99], Marker);
+    assert_eq!(segvec.get(100), None);
+    assert_eq!(segvec.iter().count(), 100);
+    assert_eq!(segvec.iter_mut().count(), 100);
+    assert_eq!(segvec.slice(10..20).iter().len(), 10);
+    assert_eq!(segvec.slice(10..20).as_slice().map(<[_]>::len), Some(10));
+    assert_eq!(segvec.chunks(30).count(), 4);
+    segvec.reverse();
+    segvec.rotate_left(7);
+    assert_eq!(segvec.binary_search(&Marker).map(|_| ()), Ok(()));
+
+    segvec.reserve(usize::MAX - 100);
+    assert_eq!(segvec.capacity(), usize::MAX);
+    assert!(segvec.try_reserve(usize::MAX - 99).is_err());
+    assert_eq!(segvec.index.len(), 0);
+
+    let segvec: SegVec<()> = SegVec::with_first_block(64);
+    assert_eq!(segvec.capacity(), usize::MAX);
+    assert_eq!(segvec.index.len(), 0);
+}
+
+#[test]
+fn iterators_are_send_and_sync() {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Iter<'_, u32>>();
+    assert_send_sync::<IterMut<'_, u32>>();
+    assert_send_sync::<crate::small::Iter<'_, u32>>();
+    assert_send_sync::<crate::small::IterMut<'_, u32>>();
+}
+
+#[test]
+fn windows_of_max_len() {
+    let mut segvec: SegVec<()> = SegVec::new();
+    // Safety: zero-sized elements don't need to be initialized.
+    unsafe { segvec.set_len(usize::MAX) };
+    assert_eq!(segvec.windows(1).len(), usize::MAX);
+    assert_eq!(segvec.windows(usize::MAX).len(), 1);
+
+    let mut windows = segvec.windows(usize::MAX);
+    assert_eq!(windows.next().map(|window| window.len()), Some(usize::MAX));
+    assert_eq!(windows.len(), 0);
+    assert!(windows.next().is_none());
+}
+
+#[test]
+fn drops_zero_sized_elements() {
+    use std::cell::Cell;
+
+    thread_local! {
+        static DROPS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    struct Counted;
+
+    impl Drop for Counted {
+        fn drop(&mut self) {
+            DROPS.with(|drops| drops.set(drops.get() + 1));
+        }
+    }
+
+    let segvec: SegVec<Counted> = (0..100).map(|_| Counted).collect();
+    assert_eq!(DROPS.with(Cell::get), 0);
+    drop(segvec);
+    assert_eq!(DROPS.with(Cell::get), 100);
+}
+
+#[cfg(feature = "allocator-api2")]
+#[test]
+fn custom_allocator() {
+    use crate::allocator::{AllocError, Allocator, Global};
+    use std::{alloc::Layout, cell::Cell, ptr::NonNull};
+
+    /// Counts the live allocations made through it.
+    #[derive(Default)]
+    struct Counting {
+        live: Cell<usize>,
+    }
+
+    unsafe impl Allocator for &'_ Counting {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            self.live.set(self.live.get() + 1);
+            Global.allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            self.live.set(self.live.get() - 1);
+            Global.deallocate(ptr, layout)
+        }
+    }
+
+    let alloc = Counting::default();
+    let mut segvec = SegVec::new_in(&alloc);
+    assert_eq!(alloc.live.get(), 0);
+
+    for i in 0..100 {
+        segvec.push(i);
+    }
+    // One allocation per data block, plus the index block.
+    assert_eq!(alloc.live.get(), segvec.index.len() + 1);
+    assert!(segvec.iter().copied().eq(0..100));
+
+    drop(segvec);
+    assert_eq!(alloc.live.get(), 0);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_serialize() {
+    use serde_test::{assert_ser_tokens, Token};
+
+    let segvec: SegVec<u32> = (0..3).collect();
+    assert_ser_tokens(
+        &segvec,
+        &[
+            Token::Seq { len: Some(3) },
+            Token::U32(0),
+            Token::U32(1),
+            Token::U32(2),
+            Token::SeqEnd,
+        ],
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_deserialize_presizes() {
+    use ::serde::de::{
+        value::{Error, SeqDeserializer},
+        Deserialize,
+    };
+
+    let deserializer = SeqDeserializer::<_, Error>::new(0..1000u32);
+    let segvec = SegVec::<u32>::deserialize(deserializer).unwrap();
+
+    // Because the sequence's length was known, all the elements should be
+    // stored in a single data block.
+    assert_eq!(
+        segvec.index.len(),
+        1,
+        "segvec={:#?}",
+        segvec.debug_details()
+    );
+    assert!(segvec.iter().copied().eq(0..1000));
+}
+
+#[cfg(feature = "rayon")]
+mod rayon {
+    use super::*;
+    use ::rayon::iter::{
+        IndexedParallelIterator, IntoParallelIterator, IntoParallelRefIterator,
+        IntoParallelRefMutIterator, ParallelExtend, ParallelIterator,
+    };
+    use std::sync::Arc;
+
+    proptest! {
+        #[test]
+        fn par_iter(vec: Vec<usize>) {
+            let segvec: SegVec<usize> = vec.iter().copied().collect();
+            let collected: Vec<&usize> = segvec.par_iter().collect();
+            prop_assert!(collected.into_iter().eq(vec.iter()));
+            let sum = |acc: usize, &elem: &usize| acc.wrapping_add(elem);
+            prop_assert_eq!(segvec.par_iter().fold(|| 0, sum).reduce(|| 0, usize::wrapping_add), vec.iter().fold(0, sum));
+        }
+
+        #[test]
+        fn par_iter_mut(vec: Vec<usize>) {
+            let mut segvec = SegVec::new();
+            for elem in &vec {
+                segvec.push(*elem);
+            }
+            segvec.par_iter_mut().for_each(|elem| *elem = elem.wrapping_mul(2));
+            prop_assert!(segvec.iter().copied().eq(vec.iter().map(|elem| elem.wrapping_mul(2))));
+
+            // Indexed adapters split at arbitrary indices.
+            segvec.par_iter_mut().enumerate().for_each(|(i, elem)| *elem = i);
+            prop_assert!(segvec.iter().copied().eq(0..vec.len()));
+        }
+
+        #[test]
+        fn into_par_iter(vec: Vec<usize>) {
+            let mut segvec = SegVec::new();
+            for elem in &vec {
+                segvec.push(*elem);
+            }
+            let mut collected: Vec<usize> = segvec.into_par_iter().rev().collect();
+            collected.reverse();
+            prop_assert_eq!(collected, vec);
+        }
+
+        #[test]
+        fn from_par_iter(vec: Vec<usize>) {
+            let segvec: SegVec<usize> = vec.par_iter().copied().collect();
+            prop_assert!(segvec.iter().eq(vec.iter()), "segvec={:#?}", segvec.debug_details());
+        }
+
+        #[test]
+        fn par_extend(vec1: Vec<usize>, vec2: Vec<usize>) {
+            let mut segvec: SegVec<usize> = vec1.iter().copied().collect();
+            segvec.par_extend(vec2.par_iter().copied());
+            prop_assert!(segvec.iter().eq(vec1.iter().chain(vec2.iter())));
+        }
+    }
+
+    #[test]
+    fn splits_on_block_boundaries() {
+        let mut segvec = SegVec::new();
+        for i in 0..100_000 {
+            segvec.push(i);
+        }
+
+        // Each piece of work is folded separately, so this collects the range
+        // of indices covered by each piece of work.
+        let pieces: Vec<(usize, usize)> = segvec
+            .par_iter()
+            .fold(Vec::new, |mut piece, &i| {
+                piece.push(i);
+                piece
+            })
+            .filter(|piece| !piece.is_empty())
+            .map(|piece| (piece[0], piece[piece.len() - 1] + 1))
+            .collect();
+
+        let mut next = 0;
+        for &(start, end) in &pieces {
+            assert_eq!(start, next, "pieces={:?}", pieces);
+            next = end;
+
+            // A piece which spans multiple data blocks must start and end on
+            // block boundaries.
+            let (first_block, first) = segvec.locate(start);
+            let (last_block, last) = segvec.locate(end - 1);
+            if first_block != last_block {
+                assert_eq!(first, 0, "pieces={:?}", pieces);
+                assert!(
+                    end == segvec.len() || last == segvec.block_len(last_block) - 1,
+                    "pieces={:?}",
+                    pieces
+                );
+            }
+        }
+        assert_eq!(next, segvec.len());
+    }
+
+    #[test]
+    fn zero_sized_elements() {
+        let mut segvec: SegVec<()> = (0..10_000).map(|_| ()).collect();
+        assert_eq!(segvec.par_iter().count(), 10_000);
+        assert_eq!(segvec.par_iter_mut().count(), 10_000);
+        segvec.par_extend((0..10_000).into_par_iter().map(|_| ()));
+        assert_eq!(segvec.into_par_iter().count(), 20_000);
+    }
+
+    #[test]
+    fn into_par_iter_drops_elements() {
+        let arc = Arc::new(());
+        let mut segvec = SegVec::new();
+        for _ in 0..1000 {
+            segvec.push(arc.clone());
+        }
+
+        // Only consume some of the elements; the rest should still be dropped.
+        let taken = segvec.into_par_iter().take(100).count();
+        assert_eq!(taken, 100);
+        assert_eq!(Arc::strong_count(&arc), 1);
+    }
+}
+
+mod concurrent {
+    use super::*;
+    use std::{sync::Arc, thread};
+
+    proptest! {
+        #[test]
+        fn push_get(vec: Vec<usize>) {
+            let segvec = ConcurrentSegVec::new();
+            for (i, elem) in vec.iter().enumerate() {
+                prop_assert_eq!(segvec.push(*elem), i);
+            }
+            prop_assert_eq!(segvec.len(), vec.len());
+            for (i, elem) in vec.iter().enumerate() {
+                prop_assert_eq!(segvec.get(i), Some(elem));
+            }
+            prop_assert_eq!(segvec.get(vec.len()), None);
+            prop_assert!(segvec.iter().eq(vec.iter()));
+        }
+    }
+
+    #[test]
+    fn push_from_many_threads() {
+        const THREADS: usize = 8;
+        const PUSHES: usize = 10_000;
+
+        let segvec = Arc::new(ConcurrentSegVec::new());
+        let threads: Vec<_> = (0..THREADS)
+            .map(|t| {
+                let segvec = segvec.clone();
+                thread::spawn(move || {
+                    for i in 0..PUSHES {
+                        let elem = t * PUSHES + i;
+                        let idx = segvec.push(elem);
+                        // Our own element is visible as soon as it's pushed.
+                        assert_eq!(segvec.get(idx), Some(&elem));
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(segvec.len(), THREADS * PUSHES);
+        let mut elems: Vec<usize> = segvec.iter().copied().collect();
+        elems.sort_unstable();
+        assert!(elems.into_iter().eq(0..THREADS * PUSHES));
+    }
+
+    #[test]
+    fn racing_threads_share_one_block() {
+        use crate::fixed_index::FixedIndex;
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Barrier,
+        };
+
+        const THREADS: usize = 8;
+        static LIVE: AtomicUsize = AtomicUsize::new(0);
+
+        struct Counted;
+
+        impl Default for Counted {
+            fn default() -> Self {
+                LIVE.fetch_add(1, Ordering::Relaxed);
+                Counted
+            }
+        }
+
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                LIVE.fetch_sub(1, Ordering::Relaxed);
+            }
+        }
+
+        // Index 1023 is the first slot of a data block of 32 slots. Every
+        // thread which loses the race frees the block it allocated.
+        let index = FixedIndex::<Counted>::new();
+        let barrier = Barrier::new(THREADS);
+        let slots = thread::scope(|scope| {
+            let threads: Vec<_> = (0..THREADS)
+                .map(|_| {
+                    scope.spawn(|| {
+                        barrier.wait();
+                        index.get_or_alloc(1023) as *const Counted as usize
+                    })
+                })
+                .collect();
+            threads
+                .into_iter()
+                .map(|thread| thread.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+        assert!(slots.iter().all(|&slot| slot == slots[0]));
+        assert_eq!(LIVE.load(Ordering::Relaxed), 32);
+
+        drop(index);
+        assert_eq!(LIVE.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn drops_elements() {
+        let arc = Arc::new(());
+        let segvec = ConcurrentSegVec::new();
+        for _ in 0..100 {
+            segvec.push(arc.clone());
+        }
+        assert_eq!(Arc::strong_count(&arc), 101);
+
+        drop(segvec);
+        assert_eq!(Arc::strong_count(&arc), 1);
+    }
+}
+
+mod log {
+    use super::*;
+    use std::{sync::Arc, thread};
+
+    proptest! {
+        #[test]
+        fn into_log(vec1: Vec<usize>, vec2: Vec<usize>) {
+            let segvec: SegVec<usize> = vec1.iter().copied().collect();
+            let (mut writer, reader) = segvec.into_log();
+            prop_assert!(reader.iter().eq(vec1.iter()));
+
+            for (i, elem) in vec2.iter().enumerate() {
+                prop_assert_eq!(writer.push(*elem), vec1.len() + i);
+            }
+            prop_assert_eq!(reader.len(), vec1.len() + vec2.len());
+            prop_assert!(reader.iter().eq(vec1.iter().chain(vec2.iter())));
+            prop_assert_eq!(reader.get(reader.len()), None);
+        }
+    }
+
+    #[test]
+    fn reuses_data_blocks() {
+        // A large first data block means the low-size blocks are skipped.
+        let mut segvec = SegVec::with_capacity(100);
+        segvec.extend(0..10usize);
+        let addresses: Vec<*const usize> = segvec.iter().map(|elem| elem as *const _).collect();
+
+        let (mut writer, reader) = segvec.into_log();
+        for (i, address) in addresses.into_iter().enumerate() {
+            assert_eq!(reader.get(i).map(|elem| elem as *const _), Some(address));
+        }
+
+        for i in 10..1000 {
+            assert_eq!(writer.push(i), i);
+        }
+        assert!(reader.iter().copied().eq(0..1000));
+    }
+
+    #[test]
+    fn zero_sized_elements() {
+        let segvec: SegVec<()> = (0..100).map(|_| ()).collect();
+        let (mut writer, reader) = segvec.into_log();
+        assert_eq!(reader.len(), 100);
+        assert_eq!(reader.get(99), Some(&()));
+        assert_eq!(writer.push(()), 100);
+        assert_eq!(reader.iter().count(), 101);
+    }
+
+    #[test]
+    fn readers_see_published_elements() {
+        const PUSHES: usize = 100_000;
+
+        let (mut writer, reader) = SegVec::new().into_log();
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let reader = reader.clone();
+                thread::spawn(move || {
+                    let mut seen = 0;
+                    while seen < PUSHES {
+                        let len = reader.len();
+                        // Every element below the published length is readable.
+                        for i in seen..len {
+                            assert_eq!(reader.get(i), Some(&i));
+                        }
+                        seen = len;
+                    }
+                })
+            })
+            .collect();
+
+        for i in 0..PUSHES {
+            writer.push(i);
+        }
+        for reader in readers {
+            reader.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn drops_elements() {
+        let arc = Arc::new(());
+        let mut segvec = SegVec::new();
+        for _ in 0..10 {
+            segvec.push(arc.clone());
+        }
+
+        let (mut writer, reader) = segvec.into_log();
+        for _ in 0..10 {
+            writer.push(arc.clone());
+        }
+        assert_eq!(Arc::strong_count(&arc), 21);
+
+        drop(writer);
+        assert_eq!(Arc::strong_count(&arc), 21);
+        drop(reader);
+        assert_eq!(Arc::strong_count(&arc), 1);
+    }
+}
+
+mod frozen {
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn push_keeps_references(vec: Vec<usize>) {
+            let segvec = FrozenSegVec::new();
+            let refs: Vec<&usize> = vec.iter().map(|elem| segvec.push(*elem)).collect();
+            prop_assert_eq!(segvec.len(), vec.len());
+            prop_assert!(refs.into_iter().eq(vec.iter()));
+            prop_assert!(segvec.iter().eq(vec.iter()));
+        }
+    }
+
+    #[test]
+    fn iter_while_pushing() {
+        let segvec: FrozenSegVec<usize> = (0..4).collect();
+        for &elem in segvec.iter() {
+            if elem < 100 {
+                segvec.push(elem + 4);
+            }
+        }
+        assert!(segvec.iter().copied().eq(0..104));
+    }
+}
+
+#[cfg(feature = "std")]
+mod once {
+    use super::*;
+    use std::{
+        collections::BTreeMap,
+        sync::atomic::{AtomicUsize, Ordering},
+        thread,
+    };
+
+    proptest! {
+        #[test]
+        fn sparse_get_or_init(entries: Vec<(u16, usize)>) {
+            let segvec = OnceSegVec::new();
+            let mut expected = BTreeMap::new();
+            for &(idx, value) in &entries {
+                let idx = idx as usize;
+                let first = *expected.entry(idx).or_insert(value);
+                prop_assert_eq!(segvec.get_or_init(idx, || value), &first);
+            }
+
+            for (&idx, value) in &expected {
+                prop_assert_eq!(segvec.get(idx), Some(value));
+            }
+            prop_assert!(segvec.iter().eq(expected.iter().map(|(&idx, value)| (idx, value))));
+        }
+    }
+
+    #[test]
+    fn initializes_once() {
+        let segvec = OnceSegVec::new();
+        let inits = AtomicUsize::new(0);
+        thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for idx in (0..1000).rev() {
+                        let value = segvec.get_or_init(idx * 7, || {
+                            inits.fetch_add(1, Ordering::Relaxed);
+                            idx
+                        });
+                        assert_eq!(*value, idx);
+                    }
+                });
+            }
+        });
+        assert_eq!(inits.load(Ordering::Relaxed), 1000);
+        assert_eq!(segvec.get(1), None);
+        assert_eq!(segvec.set(7, 0), Err(0));
+    }
+}
+
+mod sparse {
+    use super::*;
+    use proptest::{collection::vec, prelude::any};
+    use std::collections::BTreeMap;
+
+    // Large enough to leave plenty of holes, but small enough that the data
+    // blocks containing these indices aren't stupidly big.
+    const SPARSE_MAX_IDX: usize = 1 << 24;
+
+    proptest! {
+        #[test]
+        fn insert_get_remove(
+            inserts in vec((0..SPARSE_MAX_IDX, any::<usize>()), 0..100),
+            removes in vec(0..SPARSE_MAX_IDX, 0..100),
+        ) {
+            let mut segvec = SparseSegVec::new();
+            let mut expected = BTreeMap::new();
+            for &(idx, value) in &inserts {
+                prop_assert_eq!(segvec.insert(idx, value), expected.insert(idx, value));
+            }
+            // Also remove some of the indices which were inserted.
+            for &idx in removes.iter().chain(inserts.iter().step_by(2).map(|(idx, _)| idx)) {
+                prop_assert_eq!(segvec.remove(idx), expected.remove(&idx));
+            }
+
+            prop_assert_eq!(segvec.len(), expected.len());
+            for (&idx, value) in &expected {
+                prop_assert_eq!(segvec.get(idx), Some(value));
+            }
+            prop_assert!(segvec.iter().eq(expected.iter().map(|(&idx, value)| (idx, value))));
+            prop_assert_eq!(segvec.iter().len(), expected.len());
+        }
+
+        #[test]
+        fn iter_mut(inserts: Vec<(u16, usize)>) {
+            let mut segvec: SparseSegVec<usize> = inserts.iter().map(|&(idx, value)| (idx as usize, value)).collect();
+            for (idx, value) in segvec.iter_mut() {
+                *value = idx;
+            }
+            for (idx, &value) in &segvec {
+                prop_assert_eq!(idx, value);
+            }
+        }
+    }
+
+    #[test]
+    fn remove_last_element_in_block() {
+        let mut segvec = SparseSegVec::new();
+        segvec.insert(1_000_000, 1);
+        segvec.insert(10, 2);
+        assert_eq!(segvec.remove(1_000_000), Some(1));
+        assert_eq!(segvec.get(1_000_000), None);
+        assert_eq!(segvec.remove(1_000_000), None);
+        assert!(segvec.iter().eq([(10, &2)]));
+    }
+}
+
+mod idx {
+    use super::*;
+
+    crate::define_index_type! {
+        struct TestIdx = u8;
+    }
+
+    proptest! {
+        #[test]
+        fn push_index_iter(elements in proptest::collection::vec(proptest::prelude::any::<usize>(), 0..256)) {
+            let mut segvec = IndexSegVec::<TestIdx, usize>::new();
+            for (i, &element) in elements.iter().enumerate() {
+                prop_assert_eq!(segvec.next_idx(), TestIdx::from_raw(i as u8));
+                let idx = segvec.push(element);
+                prop_assert_eq!(idx.index(), i);
+                prop_assert_eq!(segvec[idx], element);
+            }
+
+            prop_assert_eq!(segvec.len(), elements.len());
+            prop_assert!(segvec.iter_enumerated().eq(elements.iter().enumerate().map(|(i, e)| (TestIdx::from_usize(i), e))));
+            prop_assert!(segvec.indices().eq(segvec.iter_enumerated().map(|(idx, _)| idx)));
+        }
+    }
+
+    #[test]
+    fn iter_mut_enumerated() {
+        let mut segvec: IndexSegVec<TestIdx, usize> = (0..100).map(|_| 0).collect();
+        for (idx, elem) in segvec.iter_mut_enumerated() {
+            *elem = idx.index() * 2;
+        }
+        let idx = TestIdx::from_raw(42);
+        assert_eq!(segvec[idx], 84);
+        segvec[idx] = 0;
+        assert_eq!(segvec.get(idx), Some(&0));
+        assert_eq!(segvec.get(TestIdx::from_raw(100)), None);
+    }
+
+    #[test]
+    #[should_panic = "out of range for TestIdx"]
+    fn push_overflows_index() {
+        let mut segvec = IndexSegVec::<TestIdx, u8>::new();
+        for _ in 0..257 {
+            segvec.push(0);
+        }
+    }
+}
+
+mod compact {
+    use super::*;
+    use std::rc::Rc;
+
+    proptest! {
+        #[test]
+        fn push_get_pop(elements: Vec<usize>, pops in 0usize..64) {
+            let mut segvec = SegVec32::new();
+            for (i, &element) in elements.iter().enumerate() {
+                prop_assert_eq!(segvec.push(element), i);
+                prop_assert!(segvec.capacity() >= segvec.len());
+            }
+
+            prop_assert_eq!(segvec.len(), elements.len());
+            for (i, element) in elements.iter().enumerate() {
+                prop_assert_eq!(segvec.get(i), Some(element));
+            }
+            prop_assert_eq!(segvec.get(elements.len()), None);
+            prop_assert!(segvec.iter().eq(elements.iter()));
+            prop_assert_eq!(segvec.iter().len(), elements.len());
+
+            let mut expected = elements.clone();
+            for _ in 0..pops {
+                prop_assert_eq!(segvec.pop(), expected.pop());
+            }
+            prop_assert!(segvec.iter().eq(expected.iter()));
+        }
+
+        #[test]
+        fn iter_mut(elements: Vec<u8>) {
+            let mut segvec: SegVec32<u8> = elements.iter().copied().collect();
+            for elem in segvec.iter_mut() {
+                *elem = elem.wrapping_add(1);
+            }
+            prop_assert!(segvec.iter().copied().eq(elements.iter().map(|elem| elem.wrapping_add(1))));
+        }
+
+        #[test]
+        fn with_capacity(capacity in 0usize..100_000) {
+            let segvec = SegVec32::<u64>::with_capacity(capacity);
+            prop_assert!(segvec.capacity() >= capacity);
+            prop_assert!(segvec.is_empty());
+        }
+    }
+
+    #[test]
+    fn drops_elements() {
+        let elem = Rc::new(());
+        let mut segvec = SegVec32::new();
+        for _ in 0..100 {
+            segvec.push(elem.clone());
+        }
+        drop(segvec.pop());
+        assert_eq!(Rc::strong_count(&elem), 100);
+        segvec.clear();
+        assert_eq!(Rc::strong_count(&elem), 1);
+
+        for _ in 0..10 {
+            segvec.push(elem.clone());
+        }
+        drop(segvec);
+        assert_eq!(Rc::strong_count(&elem), 1);
+    }
+
+    #[test]
+    fn max_len() {
+        let mut segvec = SegVec32::<u8>::new();
+        let error = segvec.try_reserve(SegVec32::<u8>::MAX_LEN + 1).unwrap_err();
+        assert_eq!(error.kind(), TryReserveErrorKind::CapacityOverflow);
+        assert_eq!(segvec.capacity(), 0);
+    }
+
+    #[test]
+    fn skips_low_size_blocks() {
+        let mut bytes = SegVec32::<u8>::new();
+        bytes.push(0);
+        assert_eq!(bytes.capacity(), 8);
+
+        let mut words = SegVec32::<u64>::new();
+        words.push(0);
+        assert_eq!(words.capacity(), 4);
+        words.extend(1..5);
+        assert_eq!(words.capacity(), 8);
+        assert!(words.iter().copied().eq(0..5));
+
+        let mut large = SegVec32::<[u8; 2048]>::new();
+        large.push([0; 2048]);
+        assert_eq!(large.capacity(), 1);
+    }
+
+    #[test]
+    fn header_is_compact() {
+        assert!(mem::size_of::<SegVec32<u64>>() <= 2 * mem::size_of::<usize>());
+        assert!(mem::size_of::<SegVec32<u64>>() < mem::size_of::<SegVec<u64>>());
+    }
+}
+
+mod stable {
+    use super::*;
+    use std::rc::Rc;
+
+    proptest! {
+        #[test]
+        fn push_get_pop(elements: Vec<usize>, pops in 0usize..64) {
+            let mut segvec = StableSegVec::new();
+            for (i, &element) in elements.iter().enumerate() {
+                prop_assert_eq!(segvec.push(element), i);
+                prop_assert!(segvec.capacity() >= segvec.len());
+            }
+
+            prop_assert_eq!(segvec.len(), elements.len());
+            for (i, element) in elements.iter().enumerate() {
+                prop_assert_eq!(segvec.get(i), Some(element));
+            }
+            prop_assert_eq!(segvec.get(elements.len()), None);
+            prop_assert!(segvec.iter().eq(elements.iter()));
+
+            let mut expected = elements.clone();
+            for _ in 0..pops {
+                prop_assert_eq!(segvec.pop(), expected.pop());
+            }
+            prop_assert!(segvec.iter().eq(expected.iter()));
+        }
+
+        #[test]
+        fn iter_mut(elements: Vec<u8>) {
+            let mut segvec: StableSegVec<u8> = elements.iter().copied().collect();
+            for elem in &mut segvec {
+                *elem = elem.wrapping_add(1);
+            }
+            prop_assert!(segvec.iter().copied().eq(elements.iter().map(|elem| elem.wrapping_add(1))));
+        }
+
+        #[test]
+        fn reserve(cap in 0..A_REASONABLE_CAPACITY) {
+            let mut segvec = StableSegVec::<u8>::with_capacity(cap);
+            prop_assert!(segvec.capacity() >= cap);
+
+            // Nothing moves when the `StableSegVec` grows.
+            segvec.push(1);
+            let first = &segvec[0] as *const u8;
+            segvec.reserve(cap * 2);
+            segvec.extend(std::iter::repeat(2).take(cap));
+            prop_assert_eq!(&segvec[0] as *const u8, first);
+        }
+    }
+
+    #[test]
+    fn drops_elements() {
+        let elem = Rc::new(());
+        let mut segvec = StableSegVec::new();
+        for _ in 0..100 {
+            segvec.push(elem.clone());
+        }
+        drop(segvec.pop());
+        assert_eq!(Rc::strong_count(&elem), 100);
+        segvec.clear();
+        assert_eq!(Rc::strong_count(&elem), 1);
+
+        for _ in 0..10 {
+            segvec.push(elem.clone());
+        }
+        drop(segvec);
+        assert_eq!(Rc::strong_count(&elem), 1);
+    }
+}
+
+mod growth {
+    use super::*;
+    use proptest::test_runner::TestCaseError;
+
+    /// Checks that `P`'s layout is consistent, and that a `SegVec` using it
+    /// behaves like a `Vec`.
+    fn check_policy<P: GrowthPolicy>(cap: usize, vec: &[usize]) -> Result<(), TestCaseError> {
+        for block in 0..32 {
+            let start = P::block_start(block);
+            let cap = P::block_capacity(block);
+            prop_assert_eq!(P::block_start(block + 1), start + cap);
+            prop_assert_eq!(P::locate(start), (block, 0));
+            prop_assert_eq!(P::locate(start + cap - 1), (block, cap - 1));
+        }
+
+        let mut segvec = SegVec::<usize, Global, P>::empty_in(Global);
+        segvec.reserve(cap);
+        prop_assert!(segvec.capacity() >= cap);
+        for (i, &elem) in vec.iter().enumerate() {
+            prop_assert_eq!(segvec.push(elem), i);
+        }
+
+        prop_assert_eq!(segvec.len(), vec.len());
+        for (i, elem) in vec.iter().enumerate() {
+            prop_assert_eq!(&segvec[i], elem, "segvec={:#?}", segvec.debug_details());
+        }
+        prop_assert!(segvec.iter().eq(vec.iter()));
+        prop_assert!(segvec
+            .slice(vec.len() / 2..)
+            .iter()
+            .eq(vec[vec.len() / 2..].iter()));
+
+        let capacity: usize = (0..segvec.index.len())
+            .map(|block| segvec.block_capacity(block))
+            .sum();
+        prop_assert_eq!(segvec.capacity(), capacity);
+        Ok(())
+    }
+
+    #[test]
+    fn brodnik_data_blocks() {
+        use crate::location::Location;
+
+        let mut loc = Location::of(0);
+        for block in 0..100_000 {
+            assert_eq!(Location::of_data_block(block), loc);
+            assert_eq!(loc.data_block(), block);
+            loc = loc.next_block();
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn brodnik(cap in 0usize..1024, vec: Vec<usize>) {
+            check_policy::<Brodnik>(cap, &vec)?;
+        }
+
+        #[test]
+        fn doubling(cap in 0usize..1024, vec: Vec<usize>) {
+            check_policy::<Doubling>(cap, &vec)?;
+        }
+
+        #[test]
+        fn fixed_chunks(cap in 0usize..1024, vec: Vec<usize>) {
+            check_policy::<FixedChunks<1>>(cap, &vec)?;
+            check_policy::<FixedChunks<16>>(cap, &vec)?;
+        }
+    }
+
+    #[test]
+    fn fixed_chunks_reserve() {
+        let mut segvec = SegVec::with_policy(FixedChunks::<16>);
+        segvec.reserve(100);
+        assert_eq!(segvec.capacity(), 112);
+        assert_eq!(segvec.index.len(), 7);
+        segvec.extend(0..113);
+        assert_eq!(segvec.capacity(), 128);
+        assert!(segvec.iter().copied().eq(0..113));
+    }
+}
+
+mod chunked {
+    use super::*;
+    use crate::chunked::ChunkedVec;
+    use proptest::{collection::vec, prelude::any};
+    use std::{collections::VecDeque, rc::Rc};
+
+    #[derive(Clone, Debug)]
+    enum Op {
+        Push(usize),
+        Pop,
+        PopFront,
+    }
+
+    fn op() -> impl proptest::strategy::Strategy<Value = Op> {
+        use proptest::prelude::*;
+        prop_oneof![
+            2 => any::<usize>().prop_map(Op::Push),
+            1 => Just(Op::Pop),
+            1 => Just(Op::PopFront),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn behaves_like_vec_deque(ops in vec(op(), 0..512)) {
+            let mut chunked = ChunkedVec::<usize, 8>::new();
+            let mut model = VecDeque::new();
+            for op in ops {
+                match op {
+                    Op::Push(elem) => {
+                        prop_assert_eq!(chunked.push(elem), model.len());
+                        model.push_back(elem);
+                    }
+                    Op::Pop => prop_assert_eq!(chunked.pop(), model.pop_back()),
+                    Op::PopFront => prop_assert_eq!(chunked.pop_front(), model.pop_front()),
+                }
+
+                prop_assert_eq!(chunked.len(), model.len());
+                prop_assert!(chunked.capacity() >= chunked.len());
+            }
+
+            prop_assert!(chunked.iter().eq(model.iter()));
+            for (i, elem) in model.iter().enumerate() {
+                prop_assert_eq!(&chunked[i], elem);
+            }
+        }
+
+        #[test]
+        fn iter_mut(elements: Vec<u8>, pops in 0usize..64) {
+            let mut chunked: ChunkedVec<u8, 4> = elements.iter().copied().collect();
+            for _ in 0..pops {
+                chunked.pop_front();
+            }
+            for elem in &mut chunked {
+                *elem = elem.wrapping_add(1);
+            }
+
+            let expected = elements.iter().skip(pops).map(|elem| elem.wrapping_add(1));
+            prop_assert!(chunked.iter().copied().eq(expected));
+        }
+
+        #[test]
+        fn queue_recycles_chunks(elements in vec(any::<usize>(), 1..256)) {
+            let mut chunked = ChunkedVec::<usize, 16>::with_capacity(32);
+            let capacity = chunked.capacity();
+            for &elem in &elements {
+                chunked.push(elem);
+                prop_assert_eq!(chunked.pop_front(), Some(elem));
+            }
+            prop_assert_eq!(chunked.capacity(), capacity);
+        }
+    }
+
+    #[test]
+    fn chunks_move_between_vecs() {
+        let mut a = ChunkedVec::<u64, 8>::with_capacity(24);
+        a.extend(0..3);
+        let mut b = ChunkedVec::new();
+        while let Some(chunk) = a.take_spare_chunk() {
+            b.add_chunk(chunk);
+        }
+        assert_eq!(a.capacity(), 8);
+        assert_eq!(b.capacity(), 16);
+
+        b.extend(0..9);
+        assert!(b.take_spare_chunk().is_none());
+        b.clear();
+        b.shrink_to_fit();
+        assert_eq!(b.capacity(), 0);
+
+        a.add_chunk(crate::chunked::Chunk::new());
+        a.extend(3..16);
+        assert_eq!(a.capacity(), 16);
+        assert!(a.iter().copied().eq(0..16));
+    }
+
+    #[test]
+    fn drops_elements() {
+        let elem = Rc::new(());
+        let mut chunked = ChunkedVec::<_, 4>::new();
+        for _ in 0..10 {
+            chunked.push(elem.clone());
+        }
+        drop(chunked.pop_front());
+        drop(chunked.pop());
+        assert_eq!(Rc::strong_count(&elem), 9);
+
+        chunked.clear();
+        assert_eq!(Rc::strong_count(&elem), 1);
+
+        chunked.extend((0..6).map(|_| elem.clone()));
+        drop(chunked);
+        assert_eq!(Rc::strong_count(&elem), 1);
+    }
+}
+
+mod small {
+    use super::*;
+    use crate::small::SmallSegVec;
+    use std::rc::Rc;
+
+    proptest! {
+        #[test]
+        fn push_get_iter(elements: Vec<usize>) {
+            let mut small = SmallSegVec::<usize, 8>::new();
+            for (i, &elem) in elements.iter().enumerate() {
+                prop_assert_eq!(small.push(elem), i);
+                prop_assert_eq!(small.spilled(), i >= 8);
+            }
+
+            prop_assert_eq!(small.len(), elements.len());
+            for (i, elem) in elements.iter().enumerate() {
+                prop_assert_eq!(&small[i], elem);
+            }
+            prop_assert_eq!(small.get(elements.len()), None);
+            prop_assert!(small.iter().eq(elements.iter()));
+            prop_assert_eq!(small.iter().len(), elements.len());
+        }
+
+        #[test]
+        fn iter_mut(elements: Vec<u8>) {
+            let mut small: SmallSegVec<u8, 4> = elements.iter().copied().collect();
+            for elem in &mut small {
+                *elem = elem.wrapping_add(1);
+            }
+            prop_assert!(small.iter().copied().eq(elements.iter().map(|elem| elem.wrapping_add(1))));
+        }
+
+        #[test]
+        fn reserve(len in 0usize..16, additional in 0usize..64) {
+            let mut small: SmallSegVec<usize, 8> = (0..len).collect();
+            small.reserve(additional);
+            prop_assert!(small.capacity() >= len + additional);
+            if len + additional <= 8 {
+                prop_assert_eq!(small.capacity(), 8);
+            }
+        }
+    }
+
+    #[test]
+    fn spill_continues_layout() {
+        let mut small = SmallSegVec::<u32, 8>::new();
+        small.extend(0..9);
+        // The first spilled data block holds as many elements as the inline
+        // storage, rather than starting over with a tiny data block.
+        assert_eq!(small.capacity(), 8 + 8);
+
+        small.push(9);
+        assert_eq!(small.capacity(), 8 + 8);
+        small.extend(10..100);
+        assert!(small.iter().copied().eq(0..100));
+    }
+
+    #[test]
+    fn no_inline_elements() {
+        let mut small = SmallSegVec::<u32, 0>::new();
+        assert!(small.is_empty());
+        assert_eq!(small.len(), 0);
+        assert_eq!(small.get(0), None);
+
+        small.extend(0..3);
+        assert!(!small.is_empty());
+        assert!(small.spilled());
+        assert_eq!(small.len(), 3);
+        assert_eq!(small.get(2), Some(&2));
+        assert_eq!(small.get(3), None);
+        assert!(small.iter().copied().eq(0..3));
+    }
+
+    #[test]
+    fn spilled_addresses_are_stable() {
+        let mut small = SmallSegVec::<u32, 2>::new();
+        small.extend(0..3);
+        let spilled = &small[2] as *const u32;
+        let small = Box::new(small);
+        assert_eq!(&small[2] as *const u32, spilled);
+    }
+
+    #[test]
+    fn drops_elements() {
+        let elem = Rc::new(());
+        let mut small = SmallSegVec::<_, 4>::new();
+        for _ in 0..10 {
+            small.push(elem.clone());
+        }
+        assert_eq!(Rc::strong_count(&elem), 11);
+        drop(small);
+        assert_eq!(Rc::strong_count(&elem), 1);
+    }
+}
+
+mod stats {
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn stats_match_layout(len in 0usize..A_REASONABLE_CAPACITY, cap in 0usize..A_REASONABLE_CAPACITY) {
+            let mut segvec: SegVec<u64> = SegVec::with_capacity(cap);
+            segvec.extend(0..len as u64);
+
+            let stats = segvec.stats();
+            prop_assert_eq!(stats.len, len);
+            prop_assert_eq!(stats.capacity, segvec.capacity());
+            prop_assert_eq!(stats.data_blocks, segvec.index.len());
+            prop_assert_eq!(stats.wasted_slots, segvec.capacity() - len);
+            prop_assert_eq!(stats.data_bytes, segvec.capacity() * mem::size_of::<u64>());
+            prop_assert!(stats.index_bytes >= segvec.index.len() * mem::size_of::<usize>());
+
+            let capacities: Vec<usize> = (0..segvec.index.len()).map(|block| segvec.block_capacity(block)).collect();
+            prop_assert_eq!(stats.largest_block, capacities.iter().copied().max());
+            prop_assert_eq!(stats.smallest_block, capacities.iter().copied().min());
+            prop_assert_eq!(stats.superblock.is_some(), !capacities.is_empty());
+
+            // Without any skipped blocks, the waste is bounded by the size of
+            // the last data block, which is O(sqrt(n)).
+            if cap == 0 {
+                if let Some(largest) = stats.largest_block {
+                    prop_assert!(stats.wasted_slots < largest);
+                    prop_assert!(largest * largest <= 4 * cmp::max(len, 4));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn empty() {
+        let segvec: SegVec<u64> = SegVec::new();
+        assert_eq!(segvec.stats(), SegVecStats::default());
+    }
+
+    #[test]
+    fn superblocks() {
+        // Superblock `k` holds `2^floor(k/2)` data blocks of `2^ceil(k/2)`
+        // elements, so 16 elements fill superblocks 0 through 3, and start
+        // superblock 4.
+        let mut segvec: SegVec<u8> = SegVec::new();
+        segvec.reserve_exact(1);
+        for i in 0..16 {
+            segvec.push(i);
+        }
+        assert_eq!(segvec.stats().superblock, Some(4));
+
+        // A large first data block skips the superblocks before it.
+        let segvec: SegVec<u8> = SegVec::with_first_block(16);
+        assert_eq!(segvec.stats().superblock, Some(7));
+
+        let segvec: SegVec<u8, Global, Doubling> = {
+            let mut segvec = SegVec::with_policy(Doubling);
+            segvec.extend(0..16);
+            segvec
+        };
+        assert_eq!(segvec.stats().superblock, None);
+    }
+
+    #[test]
+    fn zero_sized_elements() {
+        let segvec: SegVec<()> = (0..100).map(|_| ()).collect();
+        let stats = segvec.stats();
+        assert_eq!(stats.len, 100);
+        assert_eq!(stats.capacity, usize::MAX);
+        assert_eq!(stats.data_blocks, 0);
+        assert_eq!(stats.wasted_slots, 0);
+        assert_eq!(stats.data_bytes, 0);
+        assert_eq!(stats.index_bytes, 0);
+    }
+}
+
+mod details {
+    use super::*;
+
+    #[test]
+    fn display_skipped_blocks() {
+        let segvec: SegVec<u32> = SegVec::with_capacity(1000);
+        assert_eq!(
+            segvec.debug_details().to_string(),
+            "SegVec (len 0, capacity 1024, 1 data block)\n  \
+             skipped 1534 data blocks (524287 indices)\n  \
+             superblock 19:\n    \
+             data block 0 [................]    0/1024 <- empty_data_block\n",
+        );
+    }
+
+    #[test]
+    fn display_without_superblocks() {
+        let mut segvec = SegVec::with_policy(Doubling);
+        segvec.reserve_exact(1);
+        for i in 0..12 {
+            segvec.push(i);
+        }
+        assert_eq!(
+            segvec.debug_details().to_string(),
+            "SegVec (len 12, capacity 15, 4 data blocks)\n  \
+             data block 0 [#]        1/1\n  \
+             data block 1 [##]       2/2\n  \
+             data block 2 [####]     4/4\n  \
+             data block 3 [#####...] 5/8 <- empty_data_block\n",
+        );
+    }
+
+    #[test]
+    fn display_zero_sized_elements() {
+        let segvec: SegVec<()> = (0..3).map(|_| ()).collect();
+        assert_eq!(
+            segvec.debug_details().to_string(),
+            format!(
+                "SegVec (len 3, capacity {}, 0 data blocks)\n  \
+                 zero-sized elements are not stored in data blocks\n",
+                usize::MAX
+            ),
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize() {
+        use serde_test::{assert_ser_tokens, Token};
+
+        let mut segvec: SegVec<u32> = SegVec::new();
+        segvec.reserve_exact(1);
+        for i in 0..3 {
+            segvec.push(i);
+        }
+
+        let block = |layout_block, superblock, capacity, len| {
+            [
+                Token::Struct {
+                    name: "Block",
+                    len: 4,
+                },
+                Token::Str("layout_block"),
+                Token::U64(layout_block),
+                Token::Str("superblock"),
+                Token::Some,
+                Token::U64(superblock),
+                Token::Str("capacity"),
+                Token::U64(capacity),
+                Token::Str("len"),
+                Token::U64(len),
+                Token::StructEnd,
+            ]
+        };
+
+        let mut tokens = vec![
+            Token::Struct {
+                name: "SegVec",
+                len: 7,
+            },
+            Token::Str("len"),
+            Token::U64(3),
+            Token::Str("capacity"),
+            Token::U64(3),
+            Token::Str("skipped_blocks"),
+            Token::U64(0),
+            Token::Str("skipped_indices"),
+            Token::U64(0),
+            Token::Str("empty_data_block"),
+            Token::U64(1),
+            Token::Str("zero_sized"),
+            Token::Bool(false),
+            Token::Str("blocks"),
+            Token::Seq { len: Some(2) },
+        ];
+        tokens.extend(block(0, 0, 1, 1));
+        tokens.extend(block(1, 1, 2, 2));
+        tokens.extend([Token::SeqEnd, Token::StructEnd]);
+        assert_ser_tokens(&segvec.debug_details(), &tokens);
+    }
+}
+
+mod invariants {
+    use super::*;
+    use proptest::collection::vec;
+
+    #[derive(Clone, Debug)]
+    enum Op {
+        Push,
+        Extend(usize),
+        Reserve(usize),
+        ReserveExact(usize),
+        TryReserveMax,
+    }
+
+    fn op() -> impl proptest::strategy::Strategy<Value = Op> {
+        use proptest::prelude::*;
+        prop_oneof![
+            4 => Just(Op::Push),
+            1 => (0usize..64).prop_map(Op::Extend),
+            1 => (0usize..256).prop_map(Op::Reserve),
+            1 => (0usize..256).prop_map(Op::ReserveExact),
+            1 => Just(Op::TryReserveMax),
+        ]
+    }
+
+    fn run_ops<T: Default, P: GrowthPolicy>(
+        mut segvec: SegVec<T, Global, P>,
+        ops: Vec<Op>,
+    ) -> Result<(), proptest::test_runner::TestCaseError> {
+        prop_assert_eq!(segvec.check_invariants(), Ok(()));
+        for op in ops {
+            match op {
+                Op::Push => {
+                    segvec.push(T::default());
+                }
+                Op::Extend(n) => segvec.extend((0..n).map(|_| T::default())),
+                Op::Reserve(n) => segvec.reserve(n),
+                Op::ReserveExact(n) => segvec.reserve_exact(n),
+                Op::TryReserveMax => {
+                    // This only succeeds for zero-sized elements, but either
+                    // way, the `SegVec` must be left in a consistent state.
+                    let _ = segvec.try_reserve(usize::MAX);
+                }
+            }
+            prop_assert_eq!(
+                segvec.check_invariants(),
+                Ok(()),
+                "op={:?}; segvec={}",
+                op,
+                segvec.debug_details()
+            );
+        }
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn brodnik(ops in vec(op(), 0..128)) {
+            run_ops(SegVec::<u32>::new(), ops)?;
+        }
+
+        #[test]
+        fn brodnik_first_block(first_block in 0u32..10, ops in vec(op(), 0..128)) {
+            run_ops(SegVec::<u32>::with_first_block(1 << first_block), ops)?;
+        }
+
+        #[test]
+        fn doubling(ops in vec(op(), 0..128)) {
+            run_ops(SegVec::<u32, Global, Doubling>::with_policy(Doubling), ops)?;
+        }
+
+        #[test]
+        fn fixed_chunks(ops in vec(op(), 0..128)) {
+            run_ops(SegVec::<u32, Global, FixedChunks<16>>::with_policy(FixedChunks), ops)?;
+        }
+
+        #[test]
+        fn zero_sized(ops in vec(op(), 0..128)) {
+            run_ops(SegVec::<()>::new(), ops)?;
+        }
+    }
+
+    #[test]
+    fn detects_violations() {
+        let mut segvec: SegVec<u32> = (0..10).collect();
+        segvec.meta.len = 100;
+        assert_eq!(
+            segvec.check_invariants(),
+            Err(InvariantViolation::LenExceedsCapacity {
+                len: 100,
+                capacity: 16
+            })
+        );
+        segvec.meta.len = 10;
+
+        segvec.capacity = 12;
+        assert_eq!(
+            segvec.check_invariants(),
+            Err(InvariantViolation::CapacityMismatch {
+                capacity: 12,
+                expected: 16
+            })
+        );
+        segvec.capacity = 16;
+
+        let skipped_indices = segvec.meta.skipped_indices;
+        segvec.meta.skipped_indices += 1;
+        assert_eq!(
+            segvec.check_invariants(),
+            Err(InvariantViolation::SkippedIndicesMismatch {
+                skipped_indices: skipped_indices + 1,
+                expected: skipped_indices
+            })
+        );
+        segvec.meta.skipped_indices = skipped_indices;
+
+        segvec.meta.empty_data_block = 1;
+        assert_eq!(
+            segvec.check_invariants(),
+            Err(InvariantViolation::EmptyDataBlockMismatch {
+                empty_data_block: 1,
+                expected: 0
+            })
+        );
+        segvec.meta.empty_data_block = 0;
+
+        assert_eq!(segvec.check_invariants(), Ok(()));
+    }
+}